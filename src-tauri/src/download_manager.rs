@@ -1,6 +1,26 @@
+// 下载管理器：按连接限制并发数的传输队列（下载和上传），支持暂停/继续/取消、优先级、断点续传和持久化的传输历史
+use directories::ProjectDirs;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use tauri::command;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::oneshot;
+use russh_sftp::client::SftpSession;
+use crate::sftp_russh::TransferEnd;
+
+// 每个连接默认同时进行的下载数
+const DEFAULT_CONCURRENCY: usize = 3;
+// 历史中保留的已结束传输数
+const MAX_HISTORY: usize = 200;
+// 传输过程中保存进度的间隔，应用异常退出后可从此处续传
+const PROGRESS_SAVE_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileInfo {
@@ -9,6 +29,477 @@ pub struct FileInfo {
     pub modified: Option<u64>,
 }
 
+// 传输状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TransferStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, TransferStatus::Completed | TransferStatus::Failed | TransferStatus::Cancelled)
+    }
+}
+
+// 传输方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    #[default]
+    Download,
+    Upload,
+}
+
+// 传输记录（list_transfers 返回，并持久化到 transfers.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRecord {
+    pub id: u32,
+    // 只在本次运行中有效；应用重启后按 connection_key 重新绑定连接
+    pub connection_id: String,
+    // 共享SSH连接的键（profile:{id} 或 user@host:port）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub connection_key: Option<String>,
+    #[serde(default)]
+    pub direction: TransferDirection,
+    pub remote_path: String,
+    pub local_path: String,
+    pub priority: i32,
+    pub status: TransferStatus,
+    // 已连续传输的字节数，续传从此处开始
+    pub transferred: u64,
+    pub total: u64,
+    // 开始传输时远程文件的修改时间，下载续传时用于判断远程文件是否已变化
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub remote_mtime: Option<u64>,
+    // 开始上传时本地文件的大小和修改时间，上传续传时用于判断本地文件是否已变化
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub local_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub local_mtime: Option<u64>,
+    // 续传前除大小和修改时间外，再校验已传输部分的 SHA-1（需要额外读取数据，默认关闭）
+    #[serde(default)]
+    pub verify: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+// 运行中传输的停止请求
+const STOP_NONE: u8 = 0;
+const STOP_PAUSE: u8 = 1;
+const STOP_CANCEL: u8 = 2;
+
+struct Transfer {
+    record: TransferRecord,
+    stop: Arc<AtomicU8>,
+    // 等待传输结束的调用方（download_sftp_file / upload_sftp_file / wait_transfer）
+    waiters: Vec<oneshot::Sender<Result<(), String>>>,
+}
+
+struct TransferQueue {
+    transfers: Vec<Transfer>,
+    // 每个连接的并发上限
+    limits: HashMap<String, usize>,
+    // 下一个传输ID；只由后端分配，不复用历史中的ID
+    next_id: u32,
+}
+
+static QUEUE: Lazy<Mutex<TransferQueue>> = Lazy::new(|| {
+    let transfers = load_history();
+    let next_id = transfers.iter().map(|t| t.record.id).max().unwrap_or(0) + 1;
+    Mutex::new(TransferQueue { transfers, limits: HashMap::new(), next_id })
+});
+
+fn history_path() -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let dir = proj.config_dir().to_path_buf();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("transfers.json"))
+}
+
+// 读取传输历史；上次退出时未完成的传输标记为暂停，可继续
+fn load_history() -> Vec<Transfer> {
+    let records: Vec<TransferRecord> = history_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default();
+    restore_records(records)
+}
+
+fn restore_records(records: Vec<TransferRecord>) -> Vec<Transfer> {
+    records
+        .into_iter()
+        .map(|mut record| {
+            if matches!(record.status, TransferStatus::Queued | TransferStatus::Running) {
+                record.status = TransferStatus::Paused;
+            }
+            Transfer { record, stop: Arc::new(AtomicU8::new(STOP_NONE)), waiters: Vec::new() }
+        })
+        .collect()
+}
+
+// 传输历史写入线程：文件在 QUEUE 锁外写入，积压时只写最新的快照
+static HISTORY_WRITER: Lazy<crossbeam_channel::Sender<Vec<TransferRecord>>> = Lazy::new(|| {
+    let (tx, rx) = crossbeam_channel::unbounded::<Vec<TransferRecord>>();
+    thread::spawn(move || {
+        while let Ok(mut records) = rx.recv() {
+            while let Ok(newer) = rx.try_recv() {
+                records = newer;
+            }
+            write_history(&records);
+        }
+    });
+    tx
+});
+
+// 保存传输历史（只保留最近的已结束传输）；在锁内取快照，由写入线程落盘
+fn save_history(queue: &mut TransferQueue) {
+    let finished = queue.transfers.iter().filter(|t| t.record.status.is_finished()).count();
+    if finished > MAX_HISTORY {
+        let mut excess = finished - MAX_HISTORY;
+        queue.transfers.retain(|t| {
+            if excess > 0 && t.record.status.is_finished() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+    let records: Vec<TransferRecord> = queue.transfers.iter().map(|t| t.record.clone()).collect();
+    let _ = HISTORY_WRITER.send(records);
+}
+
+fn write_history(records: &[TransferRecord]) {
+    let result = history_path().and_then(|path| {
+        let data = serde_json::to_string_pretty(records).map_err(|e| e.to_string())?;
+        fs::write(path, data).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        println!("保存传输历史失败: {}", e);
+    }
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// 已结束传输的结果
+fn outcome(status: TransferStatus, error: Option<String>) -> Result<(), String> {
+    match status {
+        TransferStatus::Completed => Ok(()),
+        TransferStatus::Cancelled => Err("传输已取消".to_string()),
+        _ => Err(error.unwrap_or_else(|| "传输失败".to_string())),
+    }
+}
+
+// 更新传输状态，通知前端（transfer-status 事件），结束时唤醒等待者
+fn set_status(app: &AppHandle, queue: &mut TransferQueue, id: u32, status: TransferStatus, error: Option<String>) {
+    let transfer = match queue.transfers.iter_mut().find(|t| t.record.id == id) {
+        Some(t) => t,
+        None => return,
+    };
+    transfer.record.status = status;
+    transfer.record.error = error.clone();
+    if status.is_finished() {
+        transfer.record.finished_at = Some(now());
+        let result = outcome(status, error);
+        for waiter in transfer.waiters.drain(..) {
+            let _ = waiter.send(result.clone());
+        }
+    }
+    let _ = app.emit("transfer-status", transfer.record.clone());
+    save_history(queue);
+}
+
+// 加入下载队列；返回传输ID和传输结束时的通知
+pub fn submit(
+    app: &AppHandle,
+    connection_id: String,
+    remote_path: String,
+    local_path: String,
+    priority: i32,
+) -> Result<(u32, oneshot::Receiver<Result<(), String>>), String> {
+    enqueue(app, TransferRequest {
+        direction: TransferDirection::Download,
+        connection_id,
+        remote_path,
+        local_path,
+        priority,
+        verify: false,
+    })
+}
+
+// 加入上传队列
+pub fn submit_upload(
+    app: &AppHandle,
+    connection_id: String,
+    local_path: String,
+    remote_path: String,
+    priority: i32,
+) -> Result<(u32, oneshot::Receiver<Result<(), String>>), String> {
+    enqueue(app, TransferRequest {
+        direction: TransferDirection::Upload,
+        connection_id,
+        remote_path,
+        local_path,
+        priority,
+        verify: false,
+    })
+}
+
+// 加入队列的传输
+struct TransferRequest {
+    direction: TransferDirection,
+    connection_id: String,
+    remote_path: String,
+    local_path: String,
+    priority: i32,
+    verify: bool,
+}
+
+// 传输ID总是由后端分配；同一文件已有失败或暂停的记录时重新排队该记录，保留进度以便续传
+fn enqueue(app: &AppHandle, request: TransferRequest) -> Result<(u32, oneshot::Receiver<Result<(), String>>), String> {
+    let TransferRequest { direction, connection_id, remote_path, local_path, priority, verify } = request;
+    let connection_key = crate::sftp_russh::connection_key(&connection_id);
+    let (done_tx, done_rx) = oneshot::channel();
+    let mut queue = QUEUE.lock();
+    let existing = queue.transfers.iter_mut().find(|t| {
+        t.record.direction == direction
+            && (t.record.connection_id == connection_id
+                || (connection_key.is_some() && t.record.connection_key == connection_key))
+            && t.record.remote_path == remote_path
+            && t.record.local_path == local_path
+            && matches!(t.record.status, TransferStatus::Failed | TransferStatus::Paused)
+    });
+    let id = match existing {
+        Some(transfer) => {
+            transfer.record.connection_id = connection_id;
+            transfer.record.connection_key = connection_key;
+            transfer.record.priority = priority;
+            transfer.record.verify = verify;
+            transfer.record.finished_at = None;
+            transfer.waiters.push(done_tx);
+            transfer.record.id
+        }
+        None => {
+            let id = queue.next_id;
+            queue.next_id += 1;
+            queue.transfers.push(Transfer {
+                record: TransferRecord {
+                    id,
+                    connection_id,
+                    connection_key,
+                    direction,
+                    remote_path,
+                    local_path,
+                    priority,
+                    status: TransferStatus::Queued,
+                    transferred: 0,
+                    total: 0,
+                    remote_mtime: None,
+                    local_size: None,
+                    local_mtime: None,
+                    verify,
+                    error: None,
+                    created_at: now(),
+                    finished_at: None,
+                },
+                stop: Arc::new(AtomicU8::new(STOP_NONE)),
+                waiters: vec![done_tx],
+            });
+            id
+        }
+    };
+    set_status(app, &mut queue, id, TransferStatus::Queued, None);
+    println!("加入传输队列: {}", id);
+    schedule(app, &mut queue);
+    Ok((id, done_rx))
+}
+
+// 按优先级（高者先）和加入顺序启动排队中的传输，不超过每个连接的并发上限
+fn schedule(app: &AppHandle, queue: &mut TransferQueue) {
+    let mut running: HashMap<String, usize> = HashMap::new();
+    for transfer in queue.transfers.iter().filter(|t| t.record.status == TransferStatus::Running) {
+        *running.entry(transfer.record.connection_id.clone()).or_default() += 1;
+    }
+    let mut queued: Vec<usize> = (0..queue.transfers.len())
+        .filter(|&i| queue.transfers[i].record.status == TransferStatus::Queued)
+        .collect();
+    queued.sort_by_key(|&i| (-queue.transfers[i].record.priority, i));
+
+    let mut started = Vec::new();
+    for index in queued {
+        let record = &queue.transfers[index].record;
+        let limit = queue.limits.get(&record.connection_id).copied().unwrap_or(DEFAULT_CONCURRENCY);
+        let count = running.entry(record.connection_id.clone()).or_default();
+        if *count < limit {
+            *count += 1;
+            started.push((record.clone(), queue.transfers[index].stop.clone()));
+        }
+    }
+    for (record, stop) in started {
+        stop.store(STOP_NONE, Ordering::Relaxed);
+        set_status(app, queue, record.id, TransferStatus::Running, None);
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { run_transfer(app, record, stop).await });
+    }
+}
+
+// 本地文件的大小和修改时间（秒）
+fn local_stat(path: &str) -> Option<(u64, Option<u64>)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    Some((metadata.len(), modified))
+}
+
+// 确定续传位置：已传输的部分仍然存在、源文件未变化（大小和修改时间）且可选的校验通过时从该处继续，否则从头传输
+async fn resume_offset(session: &SftpSession, record: &TransferRecord) -> u64 {
+    if record.transferred == 0 {
+        return 0;
+    }
+    let remote = crate::sftp_russh::remote_stat(session, &record.remote_path).await;
+    let local = local_stat(&record.local_path);
+    let local_len = local.map(|(len, _)| len);
+    let offset = match (record.direction, remote, local_len) {
+        // 下载：远程文件的大小或修改时间变化时重新下载；本地部分文件可能比记录的进度多写了一些，以本地文件长度为准
+        (TransferDirection::Download, Some((remote_len, mtime)), Some(local_len)) => {
+            let resized = record.total > 0 && remote_len != record.total;
+            if resized || (record.remote_mtime.is_some() && mtime != record.remote_mtime) {
+                println!("远程文件已修改，重新下载: {}", record.remote_path);
+                0
+            } else {
+                local_len.min(remote_len)
+            }
+        }
+        // 上传：本地文件的大小或修改时间变化时重新上传，否则只信任记录的连续写入进度
+        (TransferDirection::Upload, Some((remote_len, _)), Some(local_len)) => {
+            let local_mtime = local.and_then(|(_, mtime)| mtime);
+            if record.local_size != Some(local_len) || record.local_mtime != local_mtime {
+                println!("本地文件已修改，重新上传: {}", record.local_path);
+                0
+            } else {
+                record.transferred.min(remote_len).min(local_len)
+            }
+        }
+        _ => 0,
+    };
+    if offset > 0 && record.verify {
+        match crate::sftp_russh::verify_prefix(&record.connection_id, session, &record.remote_path, &record.local_path, offset).await {
+            Ok(true) => {}
+            Ok(false) => {
+                println!("已传输部分校验不一致，重新传输: {}", record.local_path);
+                return 0;
+            }
+            Err(e) => {
+                println!("校验已传输部分失败（{}），重新传输", e);
+                return 0;
+            }
+        }
+    }
+    if offset > 0 {
+        println!("从 {} 字节处续传: {}", offset, record.local_path);
+    }
+    offset
+}
+
+async fn run_transfer(app: AppHandle, record: TransferRecord, stop: Arc<AtomicU8>) {
+    let id = record.id;
+    println!("开始传输: {} <-> {}", record.remote_path, record.local_path);
+    let mut last_saved = Instant::now();
+    let on_progress = |transferred: u64, total: u64| {
+        let mut queue = QUEUE.lock();
+        if let Some(t) = queue.transfers.iter_mut().find(|t| t.record.id == id) {
+            t.record.transferred = transferred;
+            t.record.total = total;
+        }
+        if last_saved.elapsed() >= PROGRESS_SAVE_INTERVAL {
+            last_saved = Instant::now();
+            save_history(&mut queue);
+        }
+    };
+    let should_stop = || stop.load(Ordering::Relaxed) != STOP_NONE;
+
+    let session = crate::sftp_russh::sftp_session(&record.connection_id);
+    let result = match &session {
+        Ok(session) => {
+            let offset = resume_offset(session, &record).await;
+            // 记录源文件当前的状态，下次续传时比较
+            let remote_mtime = crate::sftp_russh::remote_stat(session, &record.remote_path).await.and_then(|(_, mtime)| mtime);
+            let local = match record.direction {
+                TransferDirection::Upload => local_stat(&record.local_path),
+                TransferDirection::Download => None,
+            };
+            if let Some(t) = QUEUE.lock().transfers.iter_mut().find(|t| t.record.id == id) {
+                t.record.remote_mtime = remote_mtime;
+                t.record.local_size = local.map(|(len, _)| len);
+                t.record.local_mtime = local.and_then(|(_, mtime)| mtime);
+            }
+            let transfer = crate::sftp_russh::ChunkedTransfer {
+                app: &app,
+                session,
+                progress_id: Some(id),
+                offset,
+                on_progress,
+                should_stop,
+            };
+            match record.direction {
+                TransferDirection::Download => {
+                    crate::sftp_russh::download_chunked(transfer, &record.remote_path, &record.local_path).await
+                }
+                TransferDirection::Upload => {
+                    crate::sftp_russh::upload_chunked(transfer, &record.local_path, &record.remote_path).await
+                }
+            }
+        }
+        Err(e) => Err(e.clone()),
+    };
+
+    let (status, error) = match result {
+        Ok(TransferEnd::Completed) => (TransferStatus::Completed, None),
+        Ok(TransferEnd::Stopped) if stop.load(Ordering::Relaxed) == STOP_PAUSE => (TransferStatus::Paused, None),
+        Ok(TransferEnd::Stopped) => {
+            remove_partial(session.ok().as_deref(), &record).await;
+            (TransferStatus::Cancelled, None)
+        }
+        Err(e) => {
+            println!("传输失败: {}", e);
+            (TransferStatus::Failed, Some(e))
+        }
+    };
+    let mut queue = QUEUE.lock();
+    set_status(&app, &mut queue, id, status, error);
+    schedule(&app, &mut queue);
+}
+
+// 取消后删除未传输完的文件（下载删除本地文件，上传删除远程文件）
+async fn remove_partial(session: Option<&SftpSession>, record: &TransferRecord) {
+    match record.direction {
+        TransferDirection::Download => {
+            let _ = fs::remove_file(&record.local_path);
+        }
+        TransferDirection::Upload => {
+            if let Some(session) = session {
+                let _ = session.remove_file(&record.remote_path).await;
+            }
+        }
+    }
+}
+
 // 选择下载位置
 #[command]
 pub async fn select_download_location(file_name: String) -> Result<Option<String>, String> {
@@ -28,9 +519,12 @@ pub async fn select_download_location(file_name: String) -> Result<Option<String
 
 // 获取SFTP文件信息（用于下载进度计算）
 #[command]
-pub async fn get_sftp_file_info(_connection_id: String, path: String) -> Result<FileInfo, String> {
-    // 这里应该通过SFTP获取文件信息
-    // 为了演示，返回模拟数据
+pub async fn get_sftp_file_info(connection_id: String, path: String) -> Result<FileInfo, String> {
+    let session = crate::sftp_russh::sftp_session(&connection_id)?;
+    let metadata = session
+        .metadata(&path)
+        .await
+        .map_err(|e| format!("获取文件元数据失败: {}", e))?;
     let file_name = Path::new(&path)
         .file_name()
         .and_then(|n| n.to_str())
@@ -39,39 +533,173 @@ pub async fn get_sftp_file_info(_connection_id: String, path: String) -> Result<
     
     Ok(FileInfo {
         name: file_name,
-        size: 1024 * 1024 * 10, // 10MB 模拟文件大小
-        modified: Some(chrono::Utc::now().timestamp() as u64),
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
     })
 }
 
-// 带进度的下载功能（这是一个占位符，实际实现会更复杂）
+// 加入下载队列，立即返回传输ID；进度通过 download-progress，状态通过 transfer-status 事件通知
+// verify 为 true 时续传前额外校验已下载部分的 SHA-1
 #[command]
 pub async fn download_sftp_file_with_progress(
-    _connection_id: String,
-    _remote_path: String,
-    _local_path: String,
-) -> Result<(), String> {
-    // 这里应该实现实际的下载逻辑
-    // 包括分块下载和进度回调
-    
-    // 模拟下载过程
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    
-    // 实际实现应该：
-    // 1. 获取SFTP连接
-    // 2. 分块读取远程文件
-    // 3. 写入本地文件
-    // 4. 通过回调报告进度
-    
+    app: AppHandle,
+    connection_id: String,
+    remote_path: String,
+    local_path: String,
+    priority: Option<i32>,
+    verify: Option<bool>,
+) -> Result<u32, String> {
+    crate::sftp_russh::sftp_session(&connection_id)?;
+    let (id, _) = enqueue(&app, TransferRequest {
+        direction: TransferDirection::Download,
+        connection_id,
+        remote_path,
+        local_path,
+        priority: priority.unwrap_or(0),
+        verify: verify.unwrap_or(false),
+    })?;
+    Ok(id)
+}
+
+// 等待传输结束；传输ID由 download_sftp_file_with_progress 返回
+#[command]
+pub async fn wait_transfer(id: u32) -> Result<(), String> {
+    let done = {
+        let mut queue = QUEUE.lock();
+        let transfer = queue.transfers.iter_mut().find(|t| t.record.id == id).ok_or("传输任务不存在")?;
+        if transfer.record.status.is_finished() {
+            return outcome(transfer.record.status, transfer.record.error.clone());
+        }
+        let (done_tx, done_rx) = oneshot::channel();
+        transfer.waiters.push(done_tx);
+        done_rx
+    };
+    match done.await {
+        Ok(result) => result,
+        Err(_) => Err("传输任务已中断".to_string()),
+    }
+}
+
+// 取消传输（排队、暂停或进行中的传输），并删除未传输完的文件
+#[command]
+pub async fn cancel_download(app: AppHandle, download_id: u32) -> Result<(), String> {
+    println!("取消传输: {}", download_id);
+    let record = {
+        let mut queue = QUEUE.lock();
+        let transfer = queue
+            .transfers
+            .iter()
+            .find(|t| t.record.id == download_id)
+            .ok_or("传输任务不存在")?;
+        match transfer.record.status {
+            TransferStatus::Running => {
+                transfer.stop.store(STOP_CANCEL, Ordering::Relaxed);
+                return Ok(());
+            }
+            TransferStatus::Queued | TransferStatus::Paused => {
+                let record = transfer.record.clone();
+                set_status(&app, &mut queue, download_id, TransferStatus::Cancelled, None);
+                record
+            }
+            _ => return Ok(()),
+        }
+    };
+    let session = crate::sftp_russh::sftp_session(&record.connection_id).ok();
+    remove_partial(session.as_deref(), &record).await;
+    Ok(())
+}
+
+// 暂停传输：进行中的传输在当前块完成后停止，已传输的部分保留
+#[command]
+pub async fn pause_transfer(app: AppHandle, id: u32) -> Result<(), String> {
+    let mut queue = QUEUE.lock();
+    let transfer = queue.transfers.iter().find(|t| t.record.id == id).ok_or("传输任务不存在")?;
+    match transfer.record.status {
+        TransferStatus::Running => transfer.stop.store(STOP_PAUSE, Ordering::Relaxed),
+        TransferStatus::Queued => set_status(&app, &mut queue, id, TransferStatus::Paused, None),
+        _ => {}
+    }
     Ok(())
 }
 
-// 取消下载
+// 继续已暂停或失败的传输（重新排队，校验后从已传输的部分续传）
 #[command]
-pub async fn cancel_download(download_id: u32) -> Result<(), String> {
-    // 这里应该实现取消下载的逻辑
-    // 例如设置取消标志，中断IO操作等
-    println!("取消下载: {}", download_id);
+pub async fn resume_transfer(app: AppHandle, id: u32) -> Result<(), String> {
+    let (connection_id, connection_key) = {
+        let queue = QUEUE.lock();
+        let transfer = queue.transfers.iter().find(|t| t.record.id == id).ok_or("传输任务不存在")?;
+        (transfer.record.connection_id.clone(), transfer.record.connection_key.clone())
+    };
+    // 原连接已不存在（例如应用重启后）时，改用同一服务器的在线连接，没有则重新连接
+    if let (Err(_), Some(key)) = (crate::sftp_russh::sftp_session(&connection_id), connection_key) {
+        let live = match crate::sftp_russh::find_connection(&key) {
+            Some(live) => live,
+            None => crate::sftp_russh::open_connection(&app, &key).await?,
+        };
+        rebind(&mut QUEUE.lock(), id, live);
+    }
+    let mut queue = QUEUE.lock();
+    let status = queue
+        .transfers
+        .iter()
+        .find(|t| t.record.id == id)
+        .map(|t| t.record.status)
+        .ok_or("传输任务不存在")?;
+    if matches!(status, TransferStatus::Paused | TransferStatus::Failed) {
+        set_status(&app, &mut queue, id, TransferStatus::Queued, None);
+        schedule(&app, &mut queue);
+    }
+    Ok(())
+}
+
+// 把未运行的传输改为使用新的连接
+fn rebind(queue: &mut TransferQueue, id: u32, connection_id: String) {
+    if let Some(t) = queue.transfers.iter_mut().find(|t| t.record.id == id) {
+        if t.record.status != TransferStatus::Running {
+            println!("传输 {} 改用连接 {}", id, connection_id);
+            t.record.connection_id = connection_id;
+        }
+    }
+}
+
+// 调整优先级（数值大者先开始）
+#[command]
+pub async fn set_transfer_priority(app: AppHandle, id: u32, priority: i32) -> Result<(), String> {
+    let mut queue = QUEUE.lock();
+    let transfer = queue.transfers.iter_mut().find(|t| t.record.id == id).ok_or("传输任务不存在")?;
+    transfer.record.priority = priority;
+    schedule(&app, &mut queue);
+    Ok(())
+}
+
+// 设置连接的并发下载数
+#[command]
+pub async fn set_transfer_concurrency(app: AppHandle, connection_id: String, limit: usize) -> Result<(), String> {
+    if limit == 0 {
+        return Err("并发数必须大于0".to_string());
+    }
+    let mut queue = QUEUE.lock();
+    queue.limits.insert(connection_id, limit);
+    schedule(&app, &mut queue);
+    Ok(())
+}
+
+// 列出所有传输（进行中的和历史记录）
+#[command]
+pub async fn list_transfers() -> Result<Vec<TransferRecord>, String> {
+    Ok(QUEUE.lock().transfers.iter().map(|t| t.record.clone()).collect())
+}
+
+// 清除已结束的传输记录
+#[command]
+pub async fn clear_transfer_history() -> Result<(), String> {
+    let mut queue = QUEUE.lock();
+    queue.transfers.retain(|t| !t.record.status.is_finished());
+    save_history(&mut queue);
     Ok(())
 }
 
@@ -126,3 +754,55 @@ pub async fn open_file_location(path: String) -> Result<(), String> {
     println!("文件管理器已打开");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 应用重启前保存的记录：连接ID是上次运行的临时ID
+    const SAVED: &str = r#"[{
+        "id": 7,
+        "connectionId": "sftp-ssh-1700000000000-abc",
+        "connectionKey": "profile:web",
+        "direction": "download",
+        "remotePath": "/srv/backup.tar",
+        "localPath": "/tmp/backup.tar",
+        "priority": 0,
+        "status": "running",
+        "transferred": 4096,
+        "total": 8192,
+        "remoteMtime": 1700000000,
+        "createdAt": "2024-01-01 00:00:00"
+    }]"#;
+
+    #[test]
+    fn reloaded_record_resumes_on_new_connection() {
+        let records: Vec<TransferRecord> = serde_json::from_str(SAVED).unwrap();
+        let mut queue = TransferQueue { transfers: restore_records(records), limits: HashMap::new(), next_id: 8 };
+        let record = &queue.transfers[0].record;
+        assert_eq!(record.status, TransferStatus::Paused);
+        assert_eq!(record.connection_key.as_deref(), Some("profile:web"));
+
+        rebind(&mut queue, 7, "sftp-ssh-1800000000000-def".to_string());
+        let record = &queue.transfers[0].record;
+        assert_eq!(record.connection_id, "sftp-ssh-1800000000000-def");
+        assert_eq!(record.transferred, 4096);
+        assert_eq!(record.remote_mtime, Some(1700000000));
+    }
+
+    #[test]
+    fn running_transfer_keeps_its_connection() {
+        let records: Vec<TransferRecord> = serde_json::from_str(SAVED).unwrap();
+        let mut queue = TransferQueue { transfers: restore_records(records), limits: HashMap::new(), next_id: 8 };
+        queue.transfers[0].record.status = TransferStatus::Running;
+        rebind(&mut queue, 7, "other".to_string());
+        assert_eq!(queue.transfers[0].record.connection_id, "sftp-ssh-1700000000000-abc");
+    }
+
+    #[test]
+    fn record_without_connection_key_still_loads() {
+        let saved = SAVED.replace(r#""connectionKey": "profile:web","#, "");
+        let records: Vec<TransferRecord> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(records[0].connection_key, None);
+    }
+}
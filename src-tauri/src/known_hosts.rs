@@ -0,0 +1,485 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use directories::ProjectDirs;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use russh_keys::key::PublicKey;
+use russh_keys::PublicKeyBase64;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::Emitter;
+
+// known_hosts 中的单条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownHostEntry {
+    pub line: usize,
+    pub hosts: String,
+    pub hashed: bool,
+    pub key_type: String,
+    pub key: String,
+    pub fingerprint: String,
+}
+
+// 主机密钥不匹配的详细信息（发送给前端用于“主机密钥已变更”提示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyMismatch {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub known_fingerprints: Vec<String>,
+    #[serde(skip)]
+    key: String,
+}
+
+// 主机密钥校验结果
+#[derive(Debug, PartialEq)]
+enum HostKeyStatus {
+    Trusted,
+    // 该主机没有任何记录
+    Unknown,
+    // 同类型的密钥不一致，或只记录了其他类型的密钥（防止降级到其他算法）；附带已记录密钥的指纹
+    Changed(Vec<String>),
+}
+
+// 握手阶段被拒绝的主机密钥，等待用户确认
+static PENDING_MISMATCHES: Lazy<Mutex<HashMap<String, HostKeyMismatch>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 写入 known_hosts 时加锁，避免多个连接同时追加
+static STORE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// 主机密钥变更的错误信息，前端可以据此识别
+pub const HOST_KEY_CHANGED_ERROR: &str = "主机密钥已变更";
+
+// 应用自身的 known_hosts 文件（与 OpenSSH 格式兼容）
+pub fn known_hosts_path() -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let base = proj.config_dir().to_path_buf();
+    fs::create_dir_all(&base).map_err(|e| e.to_string())?;
+    Ok(base.join("known_hosts"))
+}
+
+// 用户的 OpenSSH known_hosts（只读参考）
+fn openssh_known_hosts_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+}
+
+// known_hosts 中的主机名写法：非22端口使用 [host]:port
+fn host_pattern(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn pending_key(host: &str, port: u16) -> String {
+    format!("{}:{}", host, port)
+}
+
+// 匹配哈希形式的主机名 |1|salt|hash
+fn match_hashed(entry: &str, pattern: &str) -> bool {
+    let mut parts = entry.trim_start_matches("|1|").splitn(2, '|');
+    let (salt, hash) = match (parts.next(), parts.next()) {
+        (Some(s), Some(h)) => (s, h),
+        _ => return false,
+    };
+    let (salt, hash) = match (BASE64.decode(salt), BASE64.decode(hash)) {
+        (Ok(s), Ok(h)) => (s, h),
+        _ => return false,
+    };
+    let mut mac = match Hmac::<Sha1>::new_from_slice(&salt) {
+        Ok(m) => m,
+        Err(_) => return false,
+    };
+    mac.update(pattern.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+// 匹配带通配符 * 和 ? 的主机模式
+pub(crate) fn match_wildcard(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi].eq_ignore_ascii_case(&t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ti = mark;
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+// 判断 known_hosts 的主机字段是否匹配目标主机
+fn hosts_match(hosts: &str, host: &str, port: u16) -> bool {
+    let pattern = host_pattern(host, port);
+    if hosts.starts_with("|1|") {
+        return match_hashed(hosts, &pattern);
+    }
+    let mut matched = false;
+    for item in hosts.split(',') {
+        let (negated, item) = match item.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, item),
+        };
+        if match_wildcard(item, &pattern) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
+
+// 解析 known_hosts 文件，跳过注释、@cert-authority 等标记行
+fn parse_known_hosts(content: &str) -> Vec<(KnownHostEntry, bool)> {
+    let mut entries = Vec::new();
+    for (idx, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let mut first = match fields.next() {
+            Some(f) => f,
+            None => continue,
+        };
+        let mut revoked = false;
+        if first.starts_with('@') {
+            if first != "@revoked" {
+                continue;
+            }
+            revoked = true;
+            first = match fields.next() {
+                Some(f) => f,
+                None => continue,
+            };
+        }
+        let (key_type, key) = match (fields.next(), fields.next()) {
+            (Some(t), Some(k)) => (t, k),
+            _ => continue,
+        };
+        let fingerprint = russh_keys::parse_public_key_base64(key)
+            .map(|k| k.fingerprint())
+            .unwrap_or_default();
+        entries.push((
+            KnownHostEntry {
+                line: idx + 1,
+                hosts: first.to_string(),
+                hashed: first.starts_with("|1|"),
+                key_type: key_type.to_string(),
+                key: key.to_string(),
+                fingerprint,
+            },
+            revoked,
+        ));
+    }
+    entries
+}
+
+// 在 known_hosts 内容中检查主机密钥：只有同一密钥类型的记录可以信任（与 OpenSSH 一致）
+// @revoked 记录优先，与行的先后无关；只记录了其他类型的密钥时视为变更
+fn check_content(content: &str, host: &str, port: u16, key_type: &str, key: &str) -> HostKeyStatus {
+    let mut trusted = false;
+    let mut known = Vec::new();
+    let mut other_types = Vec::new();
+    for (entry, revoked) in parse_known_hosts(content) {
+        if !hosts_match(&entry.hosts, host, port) {
+            continue;
+        }
+        if revoked {
+            if entry.key == key {
+                return HostKeyStatus::Changed(vec![entry.fingerprint]);
+            }
+            continue;
+        }
+        if entry.key_type != key_type {
+            other_types.push(entry.fingerprint);
+        } else if entry.key == key {
+            trusted = true;
+        } else {
+            known.push(entry.fingerprint);
+        }
+    }
+    if trusted {
+        HostKeyStatus::Trusted
+    } else if !known.is_empty() {
+        HostKeyStatus::Changed(known)
+    } else if !other_types.is_empty() {
+        HostKeyStatus::Changed(other_types)
+    } else {
+        HostKeyStatus::Unknown
+    }
+}
+
+// 记录新的主机密钥（首次连接时信任）
+fn append_entry(host: &str, port: u16, key_type: &str, key: &str) -> Result<(), String> {
+    let path = known_hosts_path()?;
+    let _guard = STORE_LOCK.lock();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{} {} {}", host_pattern(host, port), key_type, key).map_err(|e| e.to_string())
+}
+
+// 删除应用 known_hosts 中与主机匹配的所有记录，返回删除的条数
+fn remove_entries(host: &str, port: u16) -> Result<usize, String> {
+    let path = known_hosts_path()?;
+    let _guard = STORE_LOCK.lock();
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(_) => return Ok(0),
+    };
+    let matched: Vec<usize> = parse_known_hosts(&content)
+        .into_iter()
+        .filter(|(entry, _)| hosts_match(&entry.hosts, host, port))
+        .map(|(entry, _)| entry.line)
+        .collect();
+    if matched.is_empty() {
+        return Ok(0);
+    }
+    let mut kept = String::new();
+    for (idx, line) in content.lines().enumerate() {
+        if !matched.contains(&(idx + 1)) {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    fs::write(&path, kept).map_err(|e| e.to_string())?;
+    Ok(matched.len())
+}
+
+// 校验服务器主机密钥（供所有 russh 客户端处理器调用）
+// 应用自身的记录与 ~/.ssh/known_hosts 一起检查（吊销记录在任一文件中都生效）；都没有记录则首次信任并记录
+pub fn verify_server_key(host: &str, port: u16, server_public_key: &PublicKey) -> bool {
+    let key_type = server_public_key.name().to_string();
+    let key = server_public_key.public_key_base64();
+    let fingerprint = server_public_key.fingerprint();
+
+    let content = [known_hosts_path().ok(), openssh_known_hosts_path()]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let status = check_content(&content, host, port, &key_type, &key);
+
+    match status {
+        HostKeyStatus::Trusted => {
+            PENDING_MISMATCHES.lock().remove(&pending_key(host, port));
+            true
+        }
+        HostKeyStatus::Unknown => {
+            println!("首次连接 {}:{}，记录主机密钥 {} {}", host, port, key_type, fingerprint);
+            if let Err(e) = append_entry(host, port, &key_type, &key) {
+                println!("保存主机密钥失败: {}", e);
+            }
+            true
+        }
+        HostKeyStatus::Changed(known_fingerprints) => {
+            println!("⚠ 主机密钥不匹配 {}:{}，服务器提供 {} {}", host, port, key_type, fingerprint);
+            PENDING_MISMATCHES.lock().insert(
+                pending_key(host, port),
+                HostKeyMismatch {
+                    host: host.to_string(),
+                    port,
+                    key_type,
+                    fingerprint,
+                    known_fingerprints,
+                    key,
+                },
+            );
+            false
+        }
+    }
+}
+
+// 将连接错误转换为错误信息；若是主机密钥变更则额外发出 ssh_host_key_changed 事件
+pub fn describe_connect_error<R: tauri::Runtime, E: Emitter<R>>(
+    emitter: &E,
+    host: &str,
+    port: u16,
+    error: impl std::fmt::Display,
+) -> String {
+    let mismatch = PENDING_MISMATCHES.lock().get(&pending_key(host, port)).cloned();
+    match mismatch {
+        Some(mismatch) => {
+            let _ = emitter.emit("ssh_host_key_changed", mismatch.clone());
+            format!(
+                "{}: {}:{} 的 {} 指纹为 {}",
+                HOST_KEY_CHANGED_ERROR, host, port, mismatch.key_type, mismatch.fingerprint
+            )
+        }
+        None => format!("SSH连接失败: {}", error),
+    }
+}
+
+// 列出应用 known_hosts 中的所有记录
+#[tauri::command]
+pub fn list_known_hosts() -> Result<Vec<KnownHostEntry>, String> {
+    let path = known_hosts_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(_) => return Ok(Vec::new()),
+    };
+    Ok(parse_known_hosts(&content).into_iter().map(|(entry, _)| entry).collect())
+}
+
+// 接受服务器提供的新主机密钥，替换旧记录
+#[tauri::command]
+pub fn accept_host_key(host: String, port: u16) -> Result<(), String> {
+    let mismatch = PENDING_MISMATCHES
+        .lock()
+        .remove(&pending_key(&host, port))
+        .ok_or("没有待确认的主机密钥")?;
+    remove_entries(&host, port)?;
+    append_entry(&host, port, &mismatch.key_type, &mismatch.key)?;
+    println!("已更新主机密钥 {}:{} -> {}", host, port, mismatch.fingerprint);
+    Ok(())
+}
+
+// 拒绝待确认的主机密钥
+#[tauri::command]
+pub fn reject_host_key(host: String, port: u16) -> Result<(), String> {
+    PENDING_MISMATCHES.lock().remove(&pending_key(&host, port));
+    Ok(())
+}
+
+// 删除主机的所有已知密钥，下次连接时重新记录
+#[tauri::command]
+pub fn remove_known_host(host: String, port: u16) -> Result<usize, String> {
+    remove_entries(&host, port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519_KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIB1tZ+1PrZuKD0gv/0icNoH5nyq3yEfCeS8ZJ25oE0/Y";
+
+    #[test]
+    fn hashed_host_matches_only_its_name() {
+        // ssh-keygen -H 生成的 example.com 记录
+        let entry = "|1|nbwKBfIYtJgJ9RDc5oLzMwkIMAQ=|ptE866QMhJ/AccYFE3seuBUgTrU=";
+        assert!(hosts_match(entry, "example.com", 22));
+        assert!(!hosts_match(entry, "example.org", 22));
+        assert!(!hosts_match(entry, "example.com", 2222));
+    }
+
+    #[test]
+    fn hashed_host_with_port() {
+        // ssh-keygen -H 生成的 [example.com]:2222 记录
+        let entry = "|1|NJu5kStjUd0CluvL0ecUSf/FwO0=|9FO8Sxieu5suvbIThdQ2YrpnIgw=";
+        assert!(hosts_match(entry, "example.com", 2222));
+        assert!(!hosts_match(entry, "example.com", 22));
+    }
+
+    #[test]
+    fn malformed_hashed_host_does_not_match() {
+        assert!(!hosts_match("|1|not-base64", "example.com", 22));
+        assert!(!hosts_match("|1|!!!|???", "example.com", 22));
+    }
+
+    #[test]
+    fn plain_host_list_and_port() {
+        assert!(hosts_match("example.com,10.0.0.1", "10.0.0.1", 22));
+        assert!(hosts_match("[example.com]:2222", "example.com", 2222));
+        assert!(!hosts_match("[example.com]:2222", "example.com", 22));
+        assert!(!hosts_match("example.com", "example.com", 2222));
+        assert!(hosts_match("EXAMPLE.com", "example.COM", 22));
+    }
+
+    #[test]
+    fn wildcard_hosts() {
+        assert!(hosts_match("*.example.com", "db.example.com", 22));
+        assert!(!hosts_match("*.example.com", "example.com", 22));
+        assert!(hosts_match("host-?", "host-1", 22));
+        assert!(!hosts_match("host-?", "host-10", 22));
+        assert!(hosts_match("[*.example.com]:2222", "db.example.com", 2222));
+        assert!(!hosts_match("*.example.com", "db.example.com", 2222));
+    }
+
+    #[test]
+    fn negated_pattern_excludes_host() {
+        assert!(!hosts_match("*.example.com,!db.example.com", "db.example.com", 22));
+        assert!(hosts_match("*.example.com,!db.example.com", "web.example.com", 22));
+        assert!(!hosts_match("!db.example.com", "web.example.com", 22));
+    }
+
+    #[test]
+    fn matching_key_is_trusted() {
+        let content = format!("example.com ssh-ed25519 {}\n", ED25519_KEY);
+        let status = check_content(&content, "example.com", 22, "ssh-ed25519", ED25519_KEY);
+        assert_eq!(status, HostKeyStatus::Trusted);
+    }
+
+    #[test]
+    fn different_key_of_same_type_is_changed() {
+        let content = format!("example.com ssh-ed25519 {}\n", ED25519_KEY);
+        let status = check_content(&content, "example.com", 22, "ssh-ed25519", "AAAAother");
+        assert!(matches!(status, HostKeyStatus::Changed(_)));
+    }
+
+    #[test]
+    fn unknown_host() {
+        let content = format!("# comment\nexample.org ssh-ed25519 {}\n", ED25519_KEY);
+        let status = check_content(&content, "example.com", 22, "ssh-ed25519", ED25519_KEY);
+        assert_eq!(status, HostKeyStatus::Unknown);
+    }
+
+    #[test]
+    fn other_key_type_is_not_trusted() {
+        // 已记录 ed25519 时，服务器改用 RSA 不能绕过校验
+        let content = format!("# comment\nexample.com ssh-ed25519 {}\n", ED25519_KEY);
+        let status = check_content(&content, "example.com", 22, "ssh-rsa", "AAAArsa");
+        assert!(matches!(status, HostKeyStatus::Changed(ref known) if known.len() == 1));
+    }
+
+    #[test]
+    fn same_type_record_is_used_alongside_other_types() {
+        let content = format!("example.com ssh-rsa AAAArsa\nexample.com ssh-ed25519 {}\n", ED25519_KEY);
+        let status = check_content(&content, "example.com", 22, "ssh-ed25519", ED25519_KEY);
+        assert_eq!(status, HostKeyStatus::Trusted);
+    }
+
+    #[test]
+    fn revoked_key_is_rejected() {
+        let content = format!("@revoked * ssh-ed25519 {}\n", ED25519_KEY);
+        let status = check_content(&content, "example.com", 22, "ssh-ed25519", ED25519_KEY);
+        assert!(matches!(status, HostKeyStatus::Changed(_)));
+    }
+
+    #[test]
+    fn revocation_wins_over_earlier_trusted_line() {
+        let content = format!(
+            "example.com ssh-ed25519 {key}\n@revoked example.com ssh-ed25519 {key}\n",
+            key = ED25519_KEY
+        );
+        let status = check_content(&content, "example.com", 22, "ssh-ed25519", ED25519_KEY);
+        assert!(matches!(status, HostKeyStatus::Changed(_)));
+    }
+}
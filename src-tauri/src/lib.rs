@@ -1,12 +1,27 @@
 mod terminal;
+mod term_data;
 mod ssh;
 mod sftp_russh;
+mod sftp_dir;
+mod sftp_sync;
 mod fs;
 mod ssh_terminal_russh;
 mod system_monitor;
 mod download_manager;
 mod ssh_command;
 mod rdp;
+mod known_hosts;
+mod ssh_auth;
+mod ssh_session;
+mod port_forward;
+mod ssh_config;
+mod recorder;
+mod player;
+mod session_log;
+mod shell;
+mod telnet;
+mod serial;
+mod mosh;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -26,21 +41,85 @@ pub fn run() {
       terminal::start_pty,
       terminal::write_pty,
       terminal::resize_pty,
+      terminal::set_pty_binary_mode,
       terminal::close_pty,
+      terminal::list_ptys,
+      term_data::ack_terminal_output,
+      term_data::get_terminal_flow_metrics,
+      
+      // Shell profile commands
+      shell::detect_shells,
+      shell::list_shell_profiles,
+      shell::save_shell_profile,
+      shell::delete_shell_profile,
       
       // SSH Terminal commands
       ssh_terminal_russh::start_ssh_terminal,
       ssh_terminal_russh::write_ssh_terminal,
       ssh_terminal_russh::resize_ssh_terminal,
+      ssh_terminal_russh::set_ssh_binary_mode,
       ssh_terminal_russh::close_ssh_terminal,
       
+      // Telnet commands
+      telnet::start_telnet_terminal,
+      telnet::write_telnet_terminal,
+      telnet::resize_telnet_terminal,
+      telnet::close_telnet_terminal,
+      telnet::save_telnet_profile,
+      telnet::list_telnet_profiles,
+      telnet::delete_telnet_profile,
+      
+      // Serial commands
+      serial::list_serial_ports,
+      serial::start_serial_session,
+      serial::send_serial_break,
+      serial::save_serial_profile,
+      serial::list_serial_profiles,
+      serial::delete_serial_profile,
+      
+      // Recording and playback commands
+      recorder::start_recording,
+      recorder::stop_recording,
+      recorder::is_recording,
+      recorder::list_recordings,
+      player::start_playback,
+      player::pause_playback,
+      player::resume_playback,
+      player::seek_playback,
+      player::set_playback_speed,
+      player::stop_playback,
+      
+      // Session log commands
+      session_log::search_session_log,
+      session_log::list_session_logs,
+      session_log::delete_session_log,
+      
       // SSH profile commands
       ssh::save_ssh_profile,
       ssh::list_ssh_profiles,
       ssh::get_ssh_password,
+      ssh::get_ssh_key_passphrase,
       ssh::restart_ssh_connection,
       ssh::delete_ssh_profile,
       ssh::get_profiles_dir,
+      ssh_config::preview_ssh_config_import,
+      ssh_config::import_ssh_config,
+      
+      // Known hosts commands
+      known_hosts::list_known_hosts,
+      known_hosts::accept_host_key,
+      known_hosts::reject_host_key,
+      known_hosts::remove_known_host,
+      
+      // SSH authentication commands
+      ssh_auth::respond_ssh_auth_prompt,
+      ssh_auth::pending_ssh_auth_prompt,
+      ssh_auth::pending_shared_ssh_auth_prompts,
+      
+      // Port forwarding commands
+      port_forward::list_port_forwards,
+      port_forward::add_port_forward,
+      port_forward::remove_port_forward,
       
       // Local filesystem commands
       fs::list_files,
@@ -55,6 +134,12 @@ pub fn run() {
       sftp_russh::list_sftp_files,
       sftp_russh::download_sftp_file,
       sftp_russh::upload_sftp_file,
+      sftp_russh::cancel_upload,
+      sftp_dir::upload_sftp_directory,
+      sftp_dir::download_sftp_directory,
+      sftp_dir::cancel_dir_transfer,
+      sftp_sync::plan_sftp_sync,
+      sftp_sync::apply_sftp_sync,
       sftp_russh::read_sftp_file,
       sftp_russh::write_sftp_file,
       sftp_russh::delete_sftp_file,
@@ -73,7 +158,14 @@ pub fn run() {
       download_manager::select_download_location,
       download_manager::get_sftp_file_info,
       download_manager::download_sftp_file_with_progress,
+      download_manager::wait_transfer,
       download_manager::cancel_download,
+      download_manager::pause_transfer,
+      download_manager::resume_transfer,
+      download_manager::set_transfer_priority,
+      download_manager::set_transfer_concurrency,
+      download_manager::list_transfers,
+      download_manager::clear_transfer_history,
       download_manager::open_file_location,
       
       // SSH command execution
@@ -0,0 +1,240 @@
+// Mosh 传输：通过SSH连接启动 mosh-server，再运行本机安装的 mosh-client 经UDP连接
+// 不内置 SSP 协议实现；网络恢复和本地回显预测都由系统的 mosh-client 负责
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use std::io::{Read, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use crate::ssh::SshProfileMeta;
+use crate::ssh_session::SshConnection;
+use crate::term_data::FlowControl;
+
+// 关闭时先请求 mosh-client 正常退出（通知服务端结束），超时后强制结束
+const CLOSE_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+// mosh-server 返回的连接信息，以及用于连接的本地 mosh-client
+pub struct MoshConnect {
+    pub client: PathBuf,
+    pub addr: IpAddr,
+    pub port: u16,
+    pub key: String,
+}
+
+// 只允许安全字符，避免拼接到远程命令中时被 shell 解释
+fn is_shell_safe(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ':' | '@' | '=' | '+'))
+}
+
+// 单引号包裹，内部的单引号写成 '\''
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// 连接前的本地检查：不支持跳板机；查找 mosh-client，优先使用配置的路径，否则在 PATH 中查找
+pub fn check_prerequisites(settings: &SshProfileMeta) -> Result<PathBuf, String> {
+    // mosh 的UDP流量无法经跳板机转发
+    if !settings.jump_hosts.is_empty() {
+        return Err("Mosh 不支持跳板机，请改用SSH传输".to_string());
+    }
+    if let Some(path) = settings.mosh.client_path.as_deref().filter(|p| !p.is_empty()) {
+        if Path::new(path).is_file() {
+            return Ok(PathBuf::from(path));
+        }
+        return Err(format!("找不到配置的 mosh-client: {}", path));
+    }
+    crate::shell::find_in_path("mosh-client")
+        .ok_or_else(|| "本地未安装 mosh-client，请先安装 mosh 或在配置中指定 mosh-client 路径".to_string())
+}
+
+// 远程启动命令：mosh-server new -s -c 256 [-p 端口] [-l 变量=值 ...] [-- sh -c 启动命令]
+fn server_command(settings: &SshProfileMeta) -> Result<String, String> {
+    let options = &settings.mosh;
+    let server = options.server_path.as_deref().filter(|s| !s.is_empty()).unwrap_or("mosh-server");
+    if !is_shell_safe(server) {
+        return Err(format!("无效的 mosh-server 路径: {}", server));
+    }
+    let mut command = format!("{} new -s -c 256", server);
+    if let Some(range) = options.port_range.as_deref().filter(|r| !r.is_empty()) {
+        if !range.chars().all(|c| c.is_ascii_digit() || c == ':') {
+            return Err(format!("无效的端口范围: {}", range));
+        }
+        command.push_str(&format!(" -p {}", range));
+    }
+    // mosh-server 不接收SSH的 env 请求，环境变量通过 -l 传递
+    for (name, value) in settings.terminal.environment() {
+        let pair = format!("{}={}", name, value);
+        if is_shell_safe(&pair) {
+            command.push_str(&format!(" -l {}", pair));
+        } else {
+            println!("忽略无法传递给 mosh-server 的环境变量: {}", name);
+        }
+    }
+    // 启动命令由服务端执行，结束后继续进入登录 shell
+    if let Some(startup) = settings.terminal.startup_command.as_deref().filter(|c| !c.trim().is_empty()) {
+        let script = format!("{}\nexec \"${{SHELL:-/bin/sh}}\" -l", startup.trim_end());
+        command.push_str(&format!(" -- sh -c {}", shell_quote(&script)));
+    }
+    Ok(command)
+}
+
+// 从 mosh-server 输出中解析 "MOSH CONNECT <端口> <密钥>"
+fn parse_connect(output: &str) -> Result<(u16, String), String> {
+    for line in output.lines() {
+        let mut parts = line.trim().split_whitespace();
+        if parts.next() != Some("MOSH") || parts.next() != Some("CONNECT") {
+            continue;
+        }
+        let port = parts.next().and_then(|p| p.parse::<u16>().ok());
+        let key = parts.next();
+        if let (Some(port), Some(key)) = (port, key) {
+            return Ok((port, key.to_string()));
+        }
+    }
+    Err(format!("mosh-server 未返回连接信息: {}", output.trim()))
+}
+
+// 通过SSH连接启动 mosh-server；UDP 连接使用SSH主机解析后的地址
+pub async fn bootstrap(
+    connection: &SshConnection,
+    host: &str,
+    settings: &SshProfileMeta,
+    client: PathBuf,
+) -> Result<MoshConnect, String> {
+    let command = server_command(settings)?;
+    println!("启动 mosh-server: {}", command);
+    let output = connection
+        .exec(&command)
+        .await
+        .map_err(|e| format!("启动 mosh-server 失败（远程是否已安装 mosh？）: {}", e))?;
+    let (port, key) = parse_connect(&output)?;
+
+    let addr = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("解析主机 {} 失败: {}", host, e))?
+        .next()
+        .map(|a| a.ip())
+        .ok_or_else(|| format!("无法解析主机: {}", host))?;
+    println!("✓ mosh-server 已启动: {}:{}", addr, port);
+    Ok(MoshConnect { client, addr, port, key })
+}
+
+// 本地 mosh-client 进程，运行在伪终端中，输出按SSH终端的事件发送
+pub struct MoshClient {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    output_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MoshClient {
+    // 启动 mosh-client；返回的接收端在进程退出时收到退出码
+    pub fn spawn(
+        window: &tauri::Window,
+        id: &str,
+        connect: &MoshConnect,
+        settings: &SshProfileMeta,
+        (cols, rows): (u16, u16),
+        binary: Arc<AtomicBool>,
+        flow: Arc<FlowControl>,
+    ) -> Result<(MoshClient, oneshot::Receiver<Option<u32>>), String> {
+        let prediction = match settings.mosh.prediction.as_deref() {
+            Some(mode @ ("always" | "never" | "adaptive" | "experimental")) => mode,
+            _ => "adaptive",
+        };
+
+        let pair = native_pty_system()
+            .openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })
+            .map_err(|e| e.to_string())?;
+        let mut cmd = CommandBuilder::new(&connect.client);
+        cmd.arg(connect.addr.to_string());
+        cmd.arg(connect.port.to_string());
+        cmd.env("MOSH_KEY", &connect.key);
+        cmd.env("MOSH_PREDICTION_DISPLAY", prediction);
+        cmd.env("TERM", settings.terminal.term());
+        // mosh-client 要求 UTF-8 区域设置
+        let locale = settings.terminal.locale.clone().filter(|l| !l.is_empty()).unwrap_or_else(|| "en_US.UTF-8".to_string());
+        cmd.env("LANG", &locale);
+        cmd.env("LC_ALL", &locale);
+
+        let mut child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| format!("启动 {} 失败: {}", connect.client.display(), e))?;
+        drop(pair.slave);
+        let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+        let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+        let killer = child.clone_killer();
+
+        let (exit_tx, exit_rx) = oneshot::channel();
+        thread::spawn(move || {
+            let code = child.wait().ok().map(|status| status.exit_code());
+            let _ = exit_tx.send(code);
+        });
+
+        // Reader loop：数据交给输出线程合并成帧，发送 ssh_data://{id} / ssh_bytes://{id}
+        let (chunk_tx, chunk_rx) = crossbeam_channel::bounded::<Vec<u8>>(16);
+        let reader_flow = flow.clone();
+        thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            loop {
+                reader_flow.wait_blocking();
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        reader_flow.record_read(n);
+                        if chunk_tx.send(buf[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        let output_thread = crate::term_data::spawn_output_thread(
+            window.clone(),
+            id.to_string(),
+            "ssh_data",
+            "ssh_bytes",
+            chunk_rx,
+            binary,
+            flow,
+        );
+
+        println!("✓ mosh-client 已启动: {}:{}", connect.addr, connect.port);
+        Ok((MoshClient { master: pair.master, writer, killer, output_thread: Some(output_thread) }, exit_rx))
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        self.writer.write_all(data).map_err(|e| format!("发送数据失败: {}", e))
+    }
+
+    pub fn resize(&self, cols: u16, rows: u16) {
+        if let Err(e) = self.master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 }) {
+            println!("调整窗口大小失败: {}", e);
+        }
+    }
+
+    // 发送 mosh 的退出键（Ctrl-^ .）让客户端通知服务端结束会话，超时后强制结束
+    pub async fn close(&mut self, exited: &mut oneshot::Receiver<Option<u32>>) {
+        let _ = self.write(b"\x1e.");
+        if tokio::time::timeout(CLOSE_GRACE_PERIOD, &mut *exited).await.is_err() {
+            println!("mosh-client 未在宽限期内退出，强制结束");
+            let _ = self.killer.kill();
+        }
+    }
+
+    // 等待输出线程发送完剩余输出
+    pub async fn finish(mut self) {
+        if let Some(handle) = self.output_thread.take() {
+            drop(self.writer);
+            drop(self.master);
+            let _ = tokio::task::spawn_blocking(move || handle.join()).await;
+        }
+    }
+}
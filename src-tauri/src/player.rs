@@ -0,0 +1,336 @@
+// 录制回放：读取 asciicast v2 或 script/scriptreplay 录制，按时间向终端发送 pty://{id} 事件
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+use crossbeam_channel::RecvTimeoutError;
+use tauri::Emitter;
+
+// 录制中的一帧
+enum FrameData {
+    Output(String),
+    Resize { cols: u16, rows: u16 },
+}
+
+struct Frame {
+    time: f64, // 压缩空闲时间后的时间点（秒）
+    data: FrameData,
+}
+
+// 解析后的录制
+struct Cast {
+    width: u16,
+    height: u16,
+    frames: Vec<Frame>,
+}
+
+impl Cast {
+    fn duration(&self) -> f64 {
+        self.frames.last().map(|f| f.time).unwrap_or(0.0)
+    }
+
+    // 把相邻帧间超过 idle_limit 的空闲时间压缩为 idle_limit
+    fn compress_idle(&mut self, idle_limit: f64) {
+        let mut previous_raw = 0.0;
+        let mut shift = 0.0;
+        for frame in &mut self.frames {
+            let raw = frame.time;
+            let gap = raw - previous_raw;
+            if gap > idle_limit {
+                shift += gap - idle_limit;
+            }
+            previous_raw = raw;
+            frame.time = raw - shift;
+        }
+    }
+}
+
+// 回放控制消息
+enum PlayMsg {
+    Pause,
+    Resume,
+    Seek(f64),
+    Speed(f64),
+    Stop,
+}
+
+static PLAYERS: Lazy<Mutex<HashMap<String, crossbeam_channel::Sender<PlayMsg>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 回放信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackInfo {
+    pub width: u16,
+    pub height: u16,
+    pub duration: f64,
+    pub frames: usize,
+}
+
+// 回放进度（playback_progress://{id} 事件）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaybackProgress {
+    position: f64,
+    duration: f64,
+    paused: bool,
+    speed: f64,
+}
+
+// 解析 asciicast v2：首行为头部，之后每行一个 [时间, 类型, 数据] 事件
+fn parse_asciicast(text: &str) -> Result<Cast, String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header: serde_json::Value = lines
+        .next()
+        .ok_or("录制文件为空")
+        .and_then(|l| serde_json::from_str(l).map_err(|_| "录制文件头部格式错误"))?;
+    if header.get("version").and_then(|v| v.as_u64()) != Some(2) {
+        return Err("仅支持 asciicast v2 格式".into());
+    }
+    let width = header.get("width").and_then(|v| v.as_u64()).unwrap_or(80) as u16;
+    let height = header.get("height").and_then(|v| v.as_u64()).unwrap_or(24) as u16;
+
+    let mut frames = Vec::new();
+    for line in lines {
+        let event: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let time = event.get(0).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let kind = event.get(1).and_then(|v| v.as_str()).unwrap_or("");
+        let data = event.get(2).and_then(|v| v.as_str()).unwrap_or("");
+        let data = match kind {
+            "o" => FrameData::Output(data.to_string()),
+            "r" => {
+                let mut parts = data.split('x').map(|p| p.trim().parse::<u16>());
+                match (parts.next(), parts.next()) {
+                    (Some(Ok(cols)), Some(Ok(rows))) => FrameData::Resize { cols, rows },
+                    _ => continue,
+                }
+            }
+            // 输入和标记事件不回放
+            _ => continue,
+        };
+        frames.push(Frame { time, data });
+    }
+    Ok(Cast { width, height, frames })
+}
+
+// 解析 script 录制：timing 文件每行 "延迟 字节数"（或高级格式 "O 延迟 字节数"），数据文件首行为 script 的头部
+fn parse_script(data: &[u8], timing: &str) -> Result<Cast, String> {
+    let mut offset = if data.starts_with(b"Script started") {
+        data.iter().position(|&b| b == b'\n').map(|p| p + 1).unwrap_or(0)
+    } else {
+        0
+    };
+    let mut decoder = crate::term_data::Utf8Decoder::new();
+    let mut time = 0.0;
+    let mut frames = Vec::new();
+    for line in timing.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (kind, delay, length) = match parts.as_slice() {
+            [delay, length] => ("O", *delay, *length),
+            [kind, delay, length, ..] => (*kind, *delay, *length),
+            _ => continue,
+        };
+        let delay: f64 = delay.parse().map_err(|_| format!("timing 文件格式错误: {}", line))?;
+        time += delay;
+        // 高级格式中只有 O 记录对应数据文件中的输出
+        if kind != "O" {
+            continue;
+        }
+        let length: usize = length.parse().map_err(|_| format!("timing 文件格式错误: {}", line))?;
+        let end = (offset + length).min(data.len());
+        let text = decoder.decode(&data[offset..end]);
+        offset = end;
+        frames.push(Frame { time, data: FrameData::Output(text) });
+    }
+    Ok(Cast { width: 80, height: 24, frames })
+}
+
+fn load_cast(path: &str, timing_path: Option<&str>) -> Result<Cast, String> {
+    match timing_path {
+        Some(timing_path) => {
+            let data = fs::read(path).map_err(|e| format!("读取录制文件失败: {}", e))?;
+            let timing = fs::read_to_string(timing_path).map_err(|e| format!("读取 timing 文件失败: {}", e))?;
+            parse_script(&data, &timing)
+        }
+        None => {
+            let text = fs::read_to_string(path).map_err(|e| format!("读取录制文件失败: {}", e))?;
+            parse_asciicast(&text)
+        }
+    }
+}
+
+// 回放线程：按帧时间发送输出，处理暂停、跳转和变速
+fn run_player(
+    window: tauri::Window,
+    id: String,
+    cast: Cast,
+    (tx, rx): (crossbeam_channel::Sender<PlayMsg>, crossbeam_channel::Receiver<PlayMsg>),
+    mut speed: f64,
+    mut paused: bool,
+) {
+    let duration = cast.duration();
+    let output_event = format!("pty://{}", id);
+    let progress_event = format!("playback_progress://{}", id);
+    let emit_progress = |position: f64, paused: bool, speed: f64| {
+        let _ = window.emit(&progress_event, PlaybackProgress { position, duration, paused, speed });
+    };
+
+    let mut index = 0;
+    let mut position = 0.0;
+    let mut last_progress = Instant::now();
+    emit_progress(position, paused, speed);
+
+    loop {
+        // 暂停时只等待控制消息
+        let msg = if paused {
+            match rx.recv() {
+                Ok(msg) => Some(msg),
+                Err(_) => break,
+            }
+        } else {
+            let frame = match cast.frames.get(index) {
+                Some(f) => f,
+                None => break,
+            };
+            let wait = ((frame.time - position) / speed).max(0.0);
+            let waited_from = Instant::now();
+            match rx.recv_timeout(Duration::from_secs_f64(wait)) {
+                Err(RecvTimeoutError::Timeout) => {
+                    match &frame.data {
+                        FrameData::Output(text) => {
+                            let _ = window.emit(&output_event, text.clone());
+                        }
+                        FrameData::Resize { cols, rows } => {
+                            let _ = window.emit(&format!("playback_resize://{}", id), (*cols, *rows));
+                        }
+                    }
+                    position = frame.time;
+                    index += 1;
+                    if last_progress.elapsed() >= Duration::from_millis(250) {
+                        emit_progress(position, paused, speed);
+                        last_progress = Instant::now();
+                    }
+                    None
+                }
+                Ok(msg) => {
+                    position = (position + waited_from.elapsed().as_secs_f64() * speed).min(frame.time);
+                    Some(msg)
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        };
+
+        let changed = msg.is_some();
+        match msg {
+            None => {}
+            Some(PlayMsg::Pause) => paused = true,
+            Some(PlayMsg::Resume) => paused = false,
+            Some(PlayMsg::Speed(value)) => speed = value,
+            Some(PlayMsg::Stop) => break,
+            Some(PlayMsg::Seek(target)) => {
+                // 重置终端后一次性输出目标位置之前的全部内容
+                let target = target.clamp(0.0, duration);
+                let mut screen = String::from("\x1bc");
+                index = 0;
+                while let Some(frame) = cast.frames.get(index) {
+                    if frame.time > target {
+                        break;
+                    }
+                    if let FrameData::Output(text) = &frame.data {
+                        screen.push_str(text);
+                    }
+                    index += 1;
+                }
+                let _ = window.emit(&output_event, screen);
+                position = target;
+            }
+        }
+        if changed {
+            emit_progress(position, paused, speed);
+        }
+    }
+
+    emit_progress(position, paused, speed);
+    {
+        // 同一ID已开始新的回放时不移除
+        let mut players = PLAYERS.lock();
+        if players.get(&id).map(|current| current.same_channel(&tx)).unwrap_or(false) {
+            players.remove(&id);
+        }
+    }
+    let _ = window.emit(&format!("pty_exit://{}", id), "");
+}
+
+// 开始回放：path 为 asciicast 文件，或配合 timing_path 的 script 数据文件
+#[tauri::command]
+pub fn start_playback(
+    window: tauri::Window,
+    id: String,
+    path: String,
+    timing_path: Option<String>,
+    speed: Option<f64>,
+    idle_limit: Option<f64>,
+    paused: Option<bool>,
+) -> Result<PlaybackInfo, String> {
+    let mut cast = load_cast(&path, timing_path.as_deref())?;
+    if let Some(limit) = idle_limit.filter(|l| *l > 0.0) {
+        cast.compress_idle(limit);
+    }
+    let info = PlaybackInfo {
+        width: cast.width,
+        height: cast.height,
+        duration: cast.duration(),
+        frames: cast.frames.len(),
+    };
+
+    let (tx, rx) = crossbeam_channel::unbounded::<PlayMsg>();
+    if let Some(old) = PLAYERS.lock().insert(id.clone(), tx.clone()) {
+        let _ = old.send(PlayMsg::Stop);
+    }
+    let speed = speed.filter(|s| *s > 0.0).unwrap_or(1.0);
+    let paused = paused.unwrap_or(false);
+    thread::spawn(move || run_player(window, id, cast, (tx, rx), speed, paused));
+    Ok(info)
+}
+
+fn send(id: &str, msg: PlayMsg) -> Result<(), String> {
+    match PLAYERS.lock().get(id) {
+        Some(tx) => tx.send(msg).map_err(|e| e.to_string()),
+        None => Err("回放未找到".into()),
+    }
+}
+
+#[tauri::command]
+pub fn pause_playback(id: String) -> Result<(), String> {
+    send(&id, PlayMsg::Pause)
+}
+
+#[tauri::command]
+pub fn resume_playback(id: String) -> Result<(), String> {
+    send(&id, PlayMsg::Resume)
+}
+
+// 跳转到指定位置（秒，压缩空闲时间后的时间轴）
+#[tauri::command]
+pub fn seek_playback(id: String, position: f64) -> Result<(), String> {
+    send(&id, PlayMsg::Seek(position))
+}
+
+#[tauri::command]
+pub fn set_playback_speed(id: String, speed: f64) -> Result<(), String> {
+    if speed <= 0.0 {
+        return Err("回放速度必须大于0".into());
+    }
+    send(&id, PlayMsg::Speed(speed))
+}
+
+#[tauri::command]
+pub fn stop_playback(id: String) -> Result<(), String> {
+    send(&id, PlayMsg::Stop)
+}
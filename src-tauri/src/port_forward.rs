@@ -0,0 +1,499 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use russh::{client, Channel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use crate::ssh_session::SshConnection;
+
+// 转发类型：-L 本地转发，-R 远程转发，-D 动态转发（SOCKS5）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardKind {
+    Local,
+    Remote,
+    Dynamic,
+}
+
+// 端口转发配置（可保存在 SshProfileMeta.forwards 中）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortForwardSpec {
+    #[serde(default)]
+    pub id: String,
+    pub kind: ForwardKind,
+    // 本地/动态转发为本地监听地址，远程转发为服务器监听地址
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    pub bind_port: u16,
+    // 本地转发为远端目标，远程转发为本地目标；动态转发不使用
+    #[serde(default)]
+    pub target_host: String,
+    #[serde(default)]
+    pub target_port: u16,
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+// 返回给前端的转发状态
+#[derive(Debug, Clone, Serialize)]
+pub struct PortForwardInfo {
+    #[serde(flatten)]
+    pub spec: PortForwardSpec,
+    pub bytes_to_remote: u64,
+    pub bytes_to_local: u64,
+    pub active_connections: usize,
+    pub total_connections: u64,
+}
+
+// 运行中的转发
+struct ActiveForward {
+    spec: PortForwardSpec,
+    bytes_to_remote: AtomicU64,
+    bytes_to_local: AtomicU64,
+    active_connections: AtomicUsize,
+    total_connections: AtomicU64,
+    stop: watch::Sender<bool>,
+    // 本地/动态转发的监听任务；停止时等待其结束，确保端口已释放
+    listener: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ActiveForward {
+    fn info(&self) -> PortForwardInfo {
+        PortForwardInfo {
+            spec: self.spec.clone(),
+            bytes_to_remote: self.bytes_to_remote.load(Ordering::Relaxed),
+            bytes_to_local: self.bytes_to_local.load(Ordering::Relaxed),
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 每个SSH连接（连接键）上的转发列表
+static FORWARDS: Lazy<Mutex<HashMap<String, Vec<Arc<ActiveForward>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 远程转发的路由表：(处理器编号, 服务器端口) -> 转发
+static REMOTE_ROUTES: Lazy<Mutex<HashMap<(u64, u32), Arc<ActiveForward>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 单次读写的缓冲区大小
+const COPY_BUFFER_SIZE: usize = 32768;
+// 接受连接出错（如文件描述符耗尽）后重试前的等待时间
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// 单向复制数据并累计字节数，结束时关闭写端
+async fn copy_counting<R, W>(reader: &mut R, writer: &mut W, counter: &AtomicU64) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n]).await?;
+        counter.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    writer.shutdown().await
+}
+
+// 在本地连接和SSH通道之间双向转发数据，直到两端都关闭或转发被停止
+async fn pipe<L>(local: L, channel: Channel<client::Msg>, forward: Arc<ActiveForward>)
+where
+    L: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    let mut stop = forward.stop.subscribe();
+    forward.active_connections.fetch_add(1, Ordering::Relaxed);
+    forward.total_connections.fetch_add(1, Ordering::Relaxed);
+
+    let (mut local_read, mut local_write) = tokio::io::split(local);
+    let (mut remote_read, mut remote_write) = tokio::io::split(channel.into_stream());
+    let upload = copy_counting(&mut local_read, &mut remote_write, &forward.bytes_to_remote);
+    let download = copy_counting(&mut remote_read, &mut local_write, &forward.bytes_to_local);
+
+    tokio::select! {
+        _ = async { tokio::join!(upload, download) } => {},
+        _ = stop.changed() => {},
+    }
+
+    forward.active_connections.fetch_sub(1, Ordering::Relaxed);
+}
+
+// 本地转发（-L）：监听本地端口，每个连接打开到目标的 direct-tcpip 通道
+async fn run_local(listener: TcpListener, connection: Arc<SshConnection>, forward: Arc<ActiveForward>) {
+    let mut stop = forward.stop.subscribe();
+    loop {
+        let (socket, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    println!("本地转发接受连接失败: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            },
+            _ = stop.changed() => break,
+        };
+        let connection = connection.clone();
+        let forward = forward.clone();
+        tokio::spawn(async move {
+            let spec = &forward.spec;
+            match connection
+                .open_direct_tcpip(&spec.target_host, spec.target_port, &peer.ip().to_string(), peer.port())
+                .await
+            {
+                Ok(channel) => pipe(socket, channel, forward.clone()).await,
+                Err(e) => println!("{}", e),
+            }
+        });
+    }
+}
+
+// SOCKS5 握手，返回客户端请求的目标地址
+async fn socks5_handshake(socket: &mut TcpStream) -> Result<(String, u16), String> {
+    let io_err = |e: std::io::Error| format!("SOCKS5握手失败: {}", e);
+
+    // 问候：VER NMETHODS METHODS
+    let mut head = [0u8; 2];
+    socket.read_exact(&mut head).await.map_err(io_err)?;
+    if head[0] != 5 {
+        return Err("仅支持SOCKS5".to_string());
+    }
+    let mut methods = vec![0u8; head[1] as usize];
+    socket.read_exact(&mut methods).await.map_err(io_err)?;
+    if !methods.contains(&0) {
+        let _ = socket.write_all(&[5, 0xff]).await;
+        return Err("客户端不支持无认证方式".to_string());
+    }
+    socket.write_all(&[5, 0]).await.map_err(io_err)?;
+
+    // 请求：VER CMD RSV ATYP DST.ADDR DST.PORT
+    let mut request = [0u8; 4];
+    socket.read_exact(&mut request).await.map_err(io_err)?;
+    if request[1] != 1 {
+        let _ = socket.write_all(&[5, 7, 0, 1, 0, 0, 0, 0, 0, 0]).await;
+        return Err("仅支持CONNECT命令".to_string());
+    }
+    let host = match request[3] {
+        1 => {
+            let mut addr = [0u8; 4];
+            socket.read_exact(&mut addr).await.map_err(io_err)?;
+            std::net::Ipv4Addr::from(addr).to_string()
+        }
+        3 => {
+            let mut len = [0u8; 1];
+            socket.read_exact(&mut len).await.map_err(io_err)?;
+            let mut name = vec![0u8; len[0] as usize];
+            socket.read_exact(&mut name).await.map_err(io_err)?;
+            String::from_utf8_lossy(&name).to_string()
+        }
+        4 => {
+            let mut addr = [0u8; 16];
+            socket.read_exact(&mut addr).await.map_err(io_err)?;
+            std::net::Ipv6Addr::from(addr).to_string()
+        }
+        _ => {
+            let _ = socket.write_all(&[5, 8, 0, 1, 0, 0, 0, 0, 0, 0]).await;
+            return Err("不支持的地址类型".to_string());
+        }
+    };
+    let mut port = [0u8; 2];
+    socket.read_exact(&mut port).await.map_err(io_err)?;
+    Ok((host, u16::from_be_bytes(port)))
+}
+
+// 动态转发（-D）：本地SOCKS5代理，按请求打开 direct-tcpip 通道
+async fn run_dynamic(listener: TcpListener, connection: Arc<SshConnection>, forward: Arc<ActiveForward>) {
+    let mut stop = forward.stop.subscribe();
+    loop {
+        let (mut socket, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    println!("SOCKS代理接受连接失败: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            },
+            _ = stop.changed() => break,
+        };
+        let connection = connection.clone();
+        let forward = forward.clone();
+        tokio::spawn(async move {
+            let (host, port) = match socks5_handshake(&mut socket).await {
+                Ok(target) => target,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            match connection
+                .open_direct_tcpip(&host, port, &peer.ip().to_string(), peer.port())
+                .await
+            {
+                Ok(channel) => {
+                    if socket.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await.is_ok() {
+                        pipe(socket, channel, forward).await;
+                    }
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    let _ = socket.write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0]).await;
+                }
+            }
+        });
+    }
+}
+
+// 服务器为远程转发打开的通道：连接到本地目标并转发（由SSH处理器调用）
+pub fn accept_forwarded_channel(
+    client_id: u64,
+    channel: Channel<client::Msg>,
+    connected_address: &str,
+    connected_port: u32,
+    originator_address: &str,
+    originator_port: u32,
+) {
+    let forward = REMOTE_ROUTES.lock().get(&(client_id, connected_port)).cloned();
+    let forward = match forward {
+        Some(forward) => forward,
+        None => {
+            println!("收到未知的远程转发通道: {}:{}", connected_address, connected_port);
+            tokio::spawn(async move {
+                let _ = channel.close().await;
+            });
+            return;
+        }
+    };
+    println!(
+        "远程转发连接: {}:{} <- {}:{}",
+        connected_address, connected_port, originator_address, originator_port
+    );
+    tokio::spawn(async move {
+        let target = (forward.spec.target_host.as_str(), forward.spec.target_port);
+        match TcpStream::connect(target).await {
+            Ok(socket) => pipe(socket, channel, forward.clone()).await,
+            Err(e) => {
+                println!("远程转发连接本地目标 {}:{} 失败: {}", target.0, target.1, e);
+                let _ = channel.close().await;
+            }
+        }
+    });
+}
+
+// 规范化转发配置并生成ID
+fn normalize(mut spec: PortForwardSpec) -> Result<PortForwardSpec, String> {
+    if spec.bind_address.trim().is_empty() {
+        spec.bind_address = default_bind_address();
+    }
+    if spec.kind != ForwardKind::Dynamic && (spec.target_host.trim().is_empty() || spec.target_port == 0) {
+        return Err("转发目标地址不能为空".to_string());
+    }
+    if spec.kind == ForwardKind::Remote && spec.bind_port == 0 {
+        return Err("远程转发必须指定监听端口".to_string());
+    }
+    if spec.id.is_empty() {
+        let kind = match spec.kind {
+            ForwardKind::Local => "L",
+            ForwardKind::Remote => "R",
+            ForwardKind::Dynamic => "D",
+        };
+        spec.id = format!("{}-{}:{}", kind, spec.bind_address, spec.bind_port);
+    }
+    Ok(spec)
+}
+
+// 在连接上启动一个转发
+async fn start_forward(connection: &Arc<SshConnection>, spec: PortForwardSpec) -> Result<PortForwardInfo, String> {
+    let spec = normalize(spec)?;
+    if FORWARDS
+        .lock()
+        .get(&connection.key)
+        .map(|list| list.iter().any(|f| f.spec.id == spec.id))
+        .unwrap_or(false)
+    {
+        return Err(format!("转发已存在: {}", spec.id));
+    }
+
+    let (stop, _) = watch::channel(false);
+    let forward = Arc::new(ActiveForward {
+        spec: spec.clone(),
+        bytes_to_remote: AtomicU64::new(0),
+        bytes_to_local: AtomicU64::new(0),
+        active_connections: AtomicUsize::new(0),
+        total_connections: AtomicU64::new(0),
+        stop,
+        listener: Mutex::new(None),
+    });
+
+    match spec.kind {
+        ForwardKind::Local | ForwardKind::Dynamic => {
+            let listener = TcpListener::bind((spec.bind_address.as_str(), spec.bind_port))
+                .await
+                .map_err(|e| format!("监听 {}:{} 失败: {}", spec.bind_address, spec.bind_port, e))?;
+            let conn = connection.clone();
+            let fwd = forward.clone();
+            let task = if spec.kind == ForwardKind::Local {
+                tokio::spawn(run_local(listener, conn, fwd))
+            } else {
+                tokio::spawn(run_dynamic(listener, conn, fwd))
+            };
+            *forward.listener.lock() = Some(task);
+        }
+        ForwardKind::Remote => {
+            REMOTE_ROUTES
+                .lock()
+                .insert((connection.client_id, spec.bind_port as u32), forward.clone());
+            if let Err(e) = connection.tcpip_forward(&spec.bind_address, spec.bind_port).await {
+                REMOTE_ROUTES.lock().remove(&(connection.client_id, spec.bind_port as u32));
+                return Err(e);
+            }
+        }
+    }
+
+    println!("✓ 端口转发已启动: {}", spec.id);
+    let info = forward.info();
+    FORWARDS.lock().entry(connection.key.clone()).or_default().push(forward);
+    Ok(info)
+}
+
+// 通知转发停止，并等待监听任务结束（关闭监听端口）
+async fn shutdown(forward: &ActiveForward) {
+    let _ = forward.stop.send(true);
+    let task = forward.listener.lock().take();
+    if let Some(task) = task {
+        let _ = task.await;
+    }
+}
+
+// 停止一个转发
+async fn stop_forward(connection: &SshConnection, forward: &ActiveForward) {
+    shutdown(forward).await;
+    if forward.spec.kind == ForwardKind::Remote {
+        REMOTE_ROUTES.lock().remove(&(connection.client_id, forward.spec.bind_port as u32));
+        if let Err(e) = connection
+            .cancel_tcpip_forward(&forward.spec.bind_address, forward.spec.bind_port)
+            .await
+        {
+            println!("{}", e);
+        }
+    }
+    println!("端口转发已停止: {}", forward.spec.id);
+}
+
+// 连接建立后启动配置中保存的转发
+pub async fn start_saved_forwards(app: &AppHandle, connection: &Arc<SshConnection>) {
+    let profile_id = match &connection.profile_id {
+        Some(id) => id,
+        None => return,
+    };
+    let profile = match crate::ssh::load_ssh_profile(app, profile_id) {
+        Ok(profile) => profile,
+        Err(_) => return,
+    };
+    for spec in profile.forwards {
+        if let Err(e) = start_forward(connection, spec).await {
+            println!("启动保存的端口转发失败: {}", e);
+        }
+    }
+}
+
+// 连接断开时停止其上的所有转发，返回这些转发的配置；返回时本地监听端口已释放，可以立即重新监听
+pub async fn stop_all(connection: &SshConnection) -> Vec<PortForwardSpec> {
+    let forwards = FORWARDS.lock().remove(&connection.key).unwrap_or_default();
+    let mut specs = Vec::new();
+    for forward in forwards {
+        shutdown(&forward).await;
+        REMOTE_ROUTES.lock().remove(&(connection.client_id, forward.spec.bind_port as u32));
+        specs.push(forward.spec.clone());
+    }
+    specs
+}
+
+// 重连后在新连接上恢复转发
+pub async fn restore_forwards(connection: &Arc<SshConnection>, specs: Vec<PortForwardSpec>) {
+    for spec in specs {
+        if let Err(e) = start_forward(connection, spec).await {
+            println!("恢复端口转发失败: {}", e);
+        }
+    }
+}
+
+fn find_connection(connection_id: &str) -> Result<Arc<SshConnection>, String> {
+    crate::ssh_session::find_connection(connection_id).ok_or_else(|| "SSH连接不存在".to_string())
+}
+
+// 列出连接上的转发及流量统计
+#[tauri::command]
+pub fn list_port_forwards(connection_id: String) -> Result<Vec<PortForwardInfo>, String> {
+    let connection = find_connection(&connection_id)?;
+    let forwards = FORWARDS.lock();
+    Ok(forwards
+        .get(&connection.key)
+        .map(|list| list.iter().map(|f| f.info()).collect())
+        .unwrap_or_default())
+}
+
+// 添加转发；persist 为 true 时同时保存到连接对应的配置
+#[tauri::command]
+pub async fn add_port_forward(
+    app: AppHandle,
+    connection_id: String,
+    forward: PortForwardSpec,
+    persist: Option<bool>,
+) -> Result<PortForwardInfo, String> {
+    let connection = find_connection(&connection_id)?;
+    let info = start_forward(&connection, forward).await?;
+
+    if persist.unwrap_or(false) {
+        if let Some(profile_id) = &connection.profile_id {
+            let mut profile = crate::ssh::load_ssh_profile(&app, profile_id)?;
+            profile.forwards.retain(|f| f.id != info.spec.id);
+            profile.forwards.push(info.spec.clone());
+            crate::ssh::store_ssh_profile(&app, &profile)?;
+        }
+    }
+    Ok(info)
+}
+
+// 移除转发；persist 为 true 时同时从配置中删除
+#[tauri::command]
+pub async fn remove_port_forward(
+    app: AppHandle,
+    connection_id: String,
+    forward_id: String,
+    persist: Option<bool>,
+) -> Result<(), String> {
+    let connection = find_connection(&connection_id)?;
+    let forward = {
+        let mut forwards = FORWARDS.lock();
+        let list = forwards.entry(connection.key.clone()).or_default();
+        let index = list.iter().position(|f| f.spec.id == forward_id);
+        index.map(|i| list.remove(i))
+    };
+    match forward {
+        Some(forward) => stop_forward(&connection, &forward).await,
+        None => return Err("端口转发不存在".to_string()),
+    }
+
+    if persist.unwrap_or(false) {
+        if let Some(profile_id) = &connection.profile_id {
+            let mut profile = crate::ssh::load_ssh_profile(&app, profile_id)?;
+            profile.forwards.retain(|f| f.id != forward_id);
+            crate::ssh::store_ssh_profile(&app, &profile)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+// 终端会话录制：以 asciicast v2 格式记录本地终端和SSH终端的输出、窗口大小变化及可选的输入
+use directories::ProjectDirs;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+// 正在进行的录制
+struct Recording {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    capture_input: bool,
+}
+
+impl Recording {
+    // 写入一条事件：[秒数, 类型, 数据]
+    fn event(&mut self, kind: &str, data: &str) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let line = serde_json::json!([(elapsed * 1_000_000.0).round() / 1_000_000.0, kind, data]);
+        let _ = writeln!(self.writer, "{}", line);
+    }
+}
+
+// 全局录制管理，按终端ID索引
+static RECORDINGS: Lazy<Mutex<HashMap<String, Recording>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 录制文件信息
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingInfo {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+pub fn recordings_dir() -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let dir = proj.config_dir().join("recordings");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+// 开始录制，返回录制文件路径；同一终端已在录制时先结束旧的录制
+pub fn start(
+    id: &str,
+    cols: u16,
+    rows: u16,
+    title: Option<String>,
+    capture_input: bool,
+    path: Option<String>,
+) -> Result<String, String> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => {
+            let safe_id: String = id
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                .collect();
+            let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+            recordings_dir()?.join(format!("{}-{}.cast", stamp, safe_id))
+        }
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = File::create(&path).map_err(|e| format!("创建录制文件失败: {}", e))?;
+    let mut writer = BufWriter::new(file);
+
+    let mut header = serde_json::json!({
+        "version": 2,
+        "width": cols,
+        "height": rows,
+        "timestamp": chrono::Utc::now().timestamp(),
+        "env": { "TERM": "xterm-256color" },
+    });
+    if let Some(title) = title {
+        header["title"] = serde_json::Value::String(title);
+    }
+    writeln!(writer, "{}", header).map_err(|e| e.to_string())?;
+
+    let recording = Recording { writer, path: path.clone(), started: Instant::now(), capture_input };
+    if let Some(mut old) = RECORDINGS.lock().insert(id.to_string(), recording) {
+        let _ = old.writer.flush();
+    }
+    println!("开始录制终端 {}: {}", id, path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 结束录制，返回录制文件路径
+pub fn stop(id: &str) -> Option<String> {
+    let mut recording = RECORDINGS.lock().remove(id)?;
+    let _ = recording.writer.flush();
+    println!("结束录制终端 {}: {}", id, recording.path.display());
+    Some(recording.path.to_string_lossy().to_string())
+}
+
+// 记录终端输出
+pub fn record_output(id: &str, data: &str) {
+    if let Some(recording) = RECORDINGS.lock().get_mut(id) {
+        recording.event("o", data);
+    }
+}
+
+// 记录用户输入（仅在开启输入录制时）
+pub fn record_input(id: &str, data: &[u8]) {
+    if let Some(recording) = RECORDINGS.lock().get_mut(id) {
+        if recording.capture_input {
+            recording.event("i", &String::from_utf8_lossy(data));
+        }
+    }
+}
+
+// 记录窗口大小变化
+pub fn record_resize(id: &str, cols: u16, rows: u16) {
+    if let Some(recording) = RECORDINGS.lock().get_mut(id) {
+        recording.event("r", &format!("{}x{}", cols, rows));
+    }
+}
+
+// 开始录制终端（本地终端或SSH终端）
+#[tauri::command]
+pub fn start_recording(
+    id: String,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    title: Option<String>,
+    capture_input: Option<bool>,
+    path: Option<String>,
+) -> Result<String, String> {
+    start(
+        &id,
+        cols.unwrap_or(80),
+        rows.unwrap_or(24),
+        title,
+        capture_input.unwrap_or(false),
+        path,
+    )
+}
+
+// 结束录制
+#[tauri::command]
+pub fn stop_recording(id: String) -> Result<String, String> {
+    stop(&id).ok_or_else(|| "终端未在录制".to_string())
+}
+
+// 终端是否正在录制
+#[tauri::command]
+pub fn is_recording(id: String) -> bool {
+    RECORDINGS.lock().contains_key(&id)
+}
+
+// 列出录制目录中的录制文件（按修改时间倒序）
+#[tauri::command]
+pub fn list_recordings() -> Result<Vec<RecordingInfo>, String> {
+    let dir = recordings_dir()?;
+    let mut items = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("cast") {
+            continue;
+        }
+        let meta = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let modified = meta
+            .modified()
+            .ok()
+            .map(|t| chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string());
+        items.push(RecordingInfo {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            size: meta.len(),
+            modified,
+        });
+    }
+    items.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(items)
+}
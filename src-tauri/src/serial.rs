@@ -0,0 +1,425 @@
+// 串口终端：与本地终端相同的事件（pty://{id}、pty_exit://{id}、pty_error），write_pty/resize_pty/close_pty 也可用于串口会话
+use directories::ProjectDirs;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// 读取超时，用于定期检查会话是否已关闭
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+// 换行符转换
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    // 原样收发
+    #[default]
+    None,
+    Cr,
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerialParity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerialFlowControl {
+    #[default]
+    None,
+    Software,
+    Hardware,
+}
+
+// 串口参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialSettings {
+    pub port: String,
+    pub baud_rate: u32,
+    pub data_bits: u8,
+    pub parity: SerialParity,
+    pub stop_bits: u8,
+    pub flow_control: SerialFlowControl,
+    // 发送时回车键转换为的换行符
+    pub tx_newline: LineEnding,
+    // 设备发送的换行符，转换为终端使用的 CRLF
+    pub rx_newline: LineEnding,
+    pub local_echo: bool,
+}
+
+impl Default for SerialSettings {
+    fn default() -> Self {
+        SerialSettings {
+            port: String::new(),
+            baud_rate: 9600,
+            data_bits: 8,
+            parity: SerialParity::None,
+            stop_bits: 1,
+            flow_control: SerialFlowControl::None,
+            tx_newline: LineEnding::Cr,
+            rx_newline: LineEnding::None,
+            local_echo: false,
+        }
+    }
+}
+
+impl SerialSettings {
+    fn open(&self) -> Result<Box<dyn serialport::SerialPort>, String> {
+        if self.port.is_empty() {
+            return Err("未指定串口".into());
+        }
+        let data_bits = match self.data_bits {
+            5 => serialport::DataBits::Five,
+            6 => serialport::DataBits::Six,
+            7 => serialport::DataBits::Seven,
+            8 => serialport::DataBits::Eight,
+            other => return Err(format!("不支持的数据位: {}", other)),
+        };
+        let stop_bits = match self.stop_bits {
+            1 => serialport::StopBits::One,
+            2 => serialport::StopBits::Two,
+            other => return Err(format!("不支持的停止位: {}", other)),
+        };
+        let parity = match self.parity {
+            SerialParity::None => serialport::Parity::None,
+            SerialParity::Odd => serialport::Parity::Odd,
+            SerialParity::Even => serialport::Parity::Even,
+        };
+        let flow_control = match self.flow_control {
+            SerialFlowControl::None => serialport::FlowControl::None,
+            SerialFlowControl::Software => serialport::FlowControl::Software,
+            SerialFlowControl::Hardware => serialport::FlowControl::Hardware,
+        };
+        serialport::new(&self.port, self.baud_rate)
+            .data_bits(data_bits)
+            .stop_bits(stop_bits)
+            .parity(parity)
+            .flow_control(flow_control)
+            .timeout(READ_TIMEOUT)
+            .open()
+            .map_err(|e| format!("打开串口 {} 失败: {}", self.port, e))
+    }
+}
+
+// 串口配置文件结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialProfileMeta {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub settings: SerialSettings,
+}
+
+// 设备输出的换行转换，CR 可能与其后的 LF 分在两次读取中
+struct NewlineTranslator {
+    mode: LineEnding,
+    last_cr: bool,
+}
+
+impl NewlineTranslator {
+    fn translate(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(input.len() + 8);
+        for &byte in input {
+            match (self.mode, byte) {
+                // 只有 LF：补上 CR
+                (LineEnding::Lf, b'\n') if !self.last_cr => output.extend_from_slice(b"\r\n"),
+                // 只有 CR：补上 LF，并丢弃紧随其后的 LF
+                (LineEnding::Cr, b'\r') => output.extend_from_slice(b"\r\n"),
+                (LineEnding::Cr, b'\n') if self.last_cr => {}
+                _ => output.push(byte),
+            }
+            self.last_cr = byte == b'\r';
+        }
+        output
+    }
+}
+
+// 用户输入的回车转换为设备需要的换行符
+fn translate_input(mode: LineEnding, input: &[u8]) -> Vec<u8> {
+    let newline: &[u8] = match mode {
+        LineEnding::None | LineEnding::Cr => return input.to_vec(),
+        LineEnding::Lf => b"\n",
+        LineEnding::CrLf => b"\r\n",
+    };
+    let mut output = Vec::with_capacity(input.len());
+    for &byte in input {
+        if byte == b'\r' {
+            output.extend_from_slice(newline);
+        } else {
+            output.push(byte);
+        }
+    }
+    output
+}
+
+enum SerialMsg {
+    Write(Vec<u8>),
+    Break(Duration),
+    Close,
+}
+
+struct SerialSession {
+    sender: crossbeam_channel::Sender<SerialMsg>,
+}
+
+static SERIAL_SESSIONS: Lazy<Mutex<HashMap<String, SerialSession>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 启动串口会话：直接传入串口参数，或使用已保存的串口配置
+#[tauri::command]
+pub fn start_serial_session(
+    window: tauri::Window,
+    app: AppHandle,
+    id: String,
+    settings: Option<SerialSettings>,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    let settings = match (settings, profile_id) {
+        (Some(settings), _) => settings,
+        (None, Some(profile_id)) => load_serial_profile(&app, &profile_id)?.settings,
+        (None, None) => return Err("缺少串口参数".into()),
+    };
+    let port = settings.open()?;
+    let mut reader = port.try_clone().map_err(|e| e.to_string())?;
+    let mut writer = port;
+    println!("✓ 串口已打开: {} @ {}", settings.port, settings.baud_rate);
+
+    let (tx, rx) = crossbeam_channel::unbounded::<SerialMsg>();
+    SERIAL_SESSIONS.lock().insert(id.clone(), SerialSession { sender: tx });
+    let flow = crate::term_data::register_flow(&id);
+    let closed = Arc::new(AtomicBool::new(false));
+
+    // Reader loop：读取超时用于检查关闭标志
+    let (chunk_tx, chunk_rx) = crossbeam_channel::bounded::<Vec<u8>>(16);
+    let echo_tx = chunk_tx.clone();
+    let reader_flow = flow.clone();
+    let reader_closed = closed.clone();
+    let reader_window = window.clone();
+    let reader_id = id.clone();
+    let mut translator = NewlineTranslator { mode: settings.rx_newline, last_cr: false };
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while !reader_closed.load(Ordering::Relaxed) {
+            reader_flow.wait_blocking();
+            match reader.read(&mut buf) {
+                Ok(0) => continue,
+                Ok(n) => {
+                    reader_flow.record_read(n);
+                    if chunk_tx.send(translator.translate(&buf[..n])).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => continue,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    // 设备被拔出等错误：结束会话
+                    let _ = reader_window.emit("pty_error", format!("{}: 串口读取失败: {}", reader_id, e));
+                    if let Some(session) = SERIAL_SESSIONS.lock().get(&reader_id) {
+                        let _ = session.sender.send(SerialMsg::Close);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+
+    let output_thread = crate::term_data::spawn_output_thread(
+        window.clone(),
+        id.clone(),
+        "pty",
+        "pty_bytes",
+        chunk_rx,
+        Arc::new(AtomicBool::new(false)),
+        flow.clone(),
+    );
+
+    // Writer loop
+    thread::spawn(move || {
+        while let Ok(msg) = rx.recv() {
+            match msg {
+                SerialMsg::Write(data) => {
+                    let data = translate_input(settings.tx_newline, &data);
+                    if let Err(e) = writer.write_all(&data) {
+                        let _ = window.emit("pty_error", format!("{}: 串口写入失败: {}", id, e));
+                        break;
+                    }
+                    if settings.local_echo {
+                        let _ = echo_tx.send(data);
+                    }
+                }
+                SerialMsg::Break(duration) => {
+                    let _ = writer.set_break();
+                    thread::sleep(duration);
+                    let _ = writer.clear_break();
+                }
+                SerialMsg::Close => break,
+            }
+        }
+
+        closed.store(true, Ordering::Relaxed);
+        SERIAL_SESSIONS.lock().remove(&id);
+        drop(echo_tx);
+        crate::term_data::unregister_flow(&id, &flow);
+        let _ = output_thread.join();
+        crate::recorder::stop(&id);
+        crate::session_log::close(&id);
+        println!("串口会话结束: {}", id);
+        let _ = window.emit(&format!("pty_exit://{}", id), serde_json::json!({ "exitCode": null }));
+    });
+
+    Ok(())
+}
+
+// 供 write_pty 使用：写入串口会话，会话不存在时返回 false
+pub fn write(id: &str, data: Vec<u8>) -> bool {
+    match SERIAL_SESSIONS.lock().get(id) {
+        Some(session) => session.sender.send(SerialMsg::Write(data)).is_ok(),
+        None => false,
+    }
+}
+
+// 供 close_pty 使用：关闭串口会话，会话不存在时返回 false
+pub fn close(id: &str) -> bool {
+    match SERIAL_SESSIONS.lock().get(id) {
+        Some(session) => {
+            let _ = session.sender.send(SerialMsg::Close);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn exists(id: &str) -> bool {
+    SERIAL_SESSIONS.lock().contains_key(id)
+}
+
+// 发送 BREAK 信号（部分网络设备用于进入 ROMMON 等）
+#[tauri::command]
+pub fn send_serial_break(id: String, duration_ms: Option<u64>) -> Result<(), String> {
+    let duration = Duration::from_millis(duration_ms.unwrap_or(300));
+    match SERIAL_SESSIONS.lock().get(&id) {
+        Some(session) => session.sender.send(SerialMsg::Break(duration)).map_err(|e| e.to_string()),
+        None => Err("串口会话未找到".into()),
+    }
+}
+
+// 串口信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialPortEntry {
+    pub name: String,
+    pub port_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vid: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+}
+
+// 列出系统中的串口
+#[tauri::command]
+pub fn list_serial_ports() -> Result<Vec<SerialPortEntry>, String> {
+    let ports = serialport::available_ports().map_err(|e| format!("枚举串口失败: {}", e))?;
+    Ok(ports
+        .into_iter()
+        .map(|port| {
+            let mut entry = SerialPortEntry {
+                name: port.port_name,
+                port_type: "unknown".to_string(),
+                description: None,
+                vid: None,
+                pid: None,
+                serial_number: None,
+            };
+            match port.port_type {
+                serialport::SerialPortType::UsbPort(usb) => {
+                    entry.port_type = "usb".to_string();
+                    entry.description = match (usb.manufacturer, usb.product) {
+                        (Some(m), Some(p)) => Some(format!("{} {}", m, p)),
+                        (m, p) => m.or(p),
+                    };
+                    entry.vid = Some(usb.vid);
+                    entry.pid = Some(usb.pid);
+                    entry.serial_number = usb.serial_number;
+                }
+                serialport::SerialPortType::PciPort => entry.port_type = "pci".to_string(),
+                serialport::SerialPortType::BluetoothPort => entry.port_type = "bluetooth".to_string(),
+                serialport::SerialPortType::Unknown => {}
+            }
+            entry
+        })
+        .collect())
+}
+
+// 获取串口配置文件存储目录
+pub fn serial_profiles_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let dir = proj.config_dir().join("serial_profiles");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn load_serial_profile(app: &AppHandle, id: &str) -> Result<SerialProfileMeta, String> {
+    let path = serial_profiles_dir(app)?.join(format!("{}.json", id));
+    let txt = fs::read_to_string(path).map_err(|_| format!("串口配置不存在: {}", id))?;
+    serde_json::from_str(&txt).map_err(|e| e.to_string())
+}
+
+// 保存串口配置
+#[tauri::command]
+pub fn save_serial_profile(app: AppHandle, profile: SerialProfileMeta) -> Result<(), String> {
+    let path = serial_profiles_dir(&app)?.join(format!("{}.json", profile.id));
+    let data = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// 列出所有串口配置
+#[tauri::command]
+pub fn list_serial_profiles(app: AppHandle) -> Result<Vec<SerialProfileMeta>, String> {
+    let dir = serial_profiles_dir(&app)?;
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        if entry.path().extension().and_then(|s| s.to_str()) == Some("json") {
+            if let Ok(txt) = fs::read_to_string(entry.path()) {
+                if let Ok(meta) = serde_json::from_str::<SerialProfileMeta>(&txt) {
+                    out.push(meta);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+// 删除串口配置
+#[tauri::command]
+pub fn delete_serial_profile(app: AppHandle, profile_id: String) -> Result<(), String> {
+    let path = serial_profiles_dir(&app)?.join(format!("{}.json", profile_id));
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,505 @@
+// 会话日志：把终端输出追加写入磁盘（原始和去除 ANSI 控制序列两种），按大小轮转，按总大小和保留时间清理旧会话，并支持全文搜索
+use directories::ProjectDirs;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// 单个日志文件达到此大小后轮转
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+// 每种日志保留的轮转文件数
+const MAX_ROTATED_FILES: usize = 5;
+// 所有会话日志的总大小上限，超出时删除最旧的已结束会话
+const MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+// 已结束会话的日志保留时间
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 3600);
+
+// 去除 ANSI 控制序列的状态机（序列可能跨数据块）
+#[derive(Default, Clone, Copy)]
+enum StripState {
+    #[default]
+    Text,
+    Escape,
+    // 字符集选择等带一个参数字符的序列，如 ESC ( B
+    Designate,
+    Csi,
+    // OSC/DCS 等字符串序列，以 BEL 或 ESC \ 结束
+    Str,
+    StrEscape,
+}
+
+#[derive(Default)]
+struct AnsiStripper {
+    state: StripState,
+}
+
+impl AnsiStripper {
+    fn strip(&mut self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        for c in input.chars() {
+            self.state = match self.state {
+                StripState::Text => match c {
+                    '\x1b' => StripState::Escape,
+                    '\n' | '\t' => {
+                        output.push(c);
+                        StripState::Text
+                    }
+                    c if c.is_control() => StripState::Text,
+                    c => {
+                        output.push(c);
+                        StripState::Text
+                    }
+                },
+                StripState::Escape => match c {
+                    '[' => StripState::Csi,
+                    ']' | 'P' | 'X' | '^' | '_' => StripState::Str,
+                    '(' | ')' | '*' | '+' | '#' | '%' => StripState::Designate,
+                    // 其余为两字节序列
+                    _ => StripState::Text,
+                },
+                StripState::Designate => StripState::Text,
+                StripState::Csi => match c {
+                    '\x40'..='\x7e' => StripState::Text,
+                    _ => StripState::Csi,
+                },
+                StripState::Str => match c {
+                    '\x07' => StripState::Text,
+                    '\x1b' => StripState::StrEscape,
+                    _ => StripState::Str,
+                },
+                StripState::StrEscape => match c {
+                    '\\' => StripState::Text,
+                    _ => StripState::Str,
+                },
+            };
+        }
+        output
+    }
+}
+
+// 可轮转的日志文件（带缓冲，搜索和关闭前刷新）
+struct RotatingFile {
+    path: PathBuf,
+    file: BufWriter<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> Result<Self, String> {
+        let file = OpenOptions::new().create(true).append(true).open(&path).map_err(|e| e.to_string())?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(RotatingFile { path, file: BufWriter::new(file), size })
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if self.size + data.len() as u64 > MAX_FILE_BYTES && self.size > 0 {
+            if let Err(e) = self.rotate() {
+                println!("会话日志轮转失败: {}", e);
+            }
+        }
+        if self.file.write_all(data).is_ok() {
+            self.size += data.len() as u64;
+        }
+    }
+
+    // name.log -> name.1.log -> ... -> name.N.log，最旧的被删除
+    fn rotate(&mut self) -> Result<(), String> {
+        let _ = self.file.flush();
+        let _ = fs::remove_file(rotated_path(&self.path, MAX_ROTATED_FILES));
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                let _ = fs::rename(&from, rotated_path(&self.path, index + 1));
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1)).map_err(|e| e.to_string())?;
+        *self = RotatingFile::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("log");
+    path.with_file_name(format!("{}.{}.log", stem, index))
+}
+
+// 一个会话的日志
+struct SessionLog {
+    raw: RotatingFile,
+    text: RotatingFile,
+    stripper: AnsiStripper,
+}
+
+impl SessionLog {
+    fn flush(&mut self) {
+        let _ = self.raw.file.flush();
+        let _ = self.text.file.flush();
+    }
+}
+
+// 每个会话的日志单独加锁，写入时不阻塞其他会话
+static SESSION_LOGS: Lazy<Mutex<HashMap<String, Arc<Mutex<SessionLog>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 是否有清理线程正在运行
+static PRUNING: AtomicBool = AtomicBool::new(false);
+
+pub fn logs_dir() -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let dir = proj.config_dir().join("logs");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn session_dir(id: &str) -> Result<PathBuf, String> {
+    let safe_id: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    Ok(logs_dir()?.join(safe_id))
+}
+
+// 目录中所有文件的总大小和最后修改时间
+fn dir_usage(dir: &Path) -> (u64, SystemTime) {
+    let mut size = 0;
+    let mut modified = SystemTime::UNIX_EPOCH;
+    for metadata in fs::read_dir(dir).into_iter().flatten().flatten().filter_map(|f| f.metadata().ok()) {
+        size += metadata.len();
+        if let Ok(time) = metadata.modified() {
+            modified = modified.max(time);
+        }
+    }
+    (size, modified)
+}
+
+// 在后台线程中清理旧日志，不阻塞终端输出；已有清理在进行时跳过
+fn spawn_prune() {
+    if PRUNING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    thread::spawn(|| {
+        let active: Vec<PathBuf> = SESSION_LOGS.lock().keys().filter_map(|id| session_dir(id).ok()).collect();
+        prune_logs(&active);
+        PRUNING.store(false, Ordering::Release);
+    });
+}
+
+// 清理已结束会话的日志：删除超过保留时间的，总大小超出上限时从最旧的开始删除
+fn prune_logs(active: &[PathBuf]) {
+    let dir = match logs_dir() {
+        Ok(dir) => dir,
+        Err(_) => return,
+    };
+    let mut total = 0;
+    let mut finished = Vec::new();
+    for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let (size, modified) = dir_usage(&path);
+        total += size;
+        if !active.contains(&path) {
+            finished.push((modified, size, path));
+        }
+    }
+    finished.sort_by_key(|(modified, _, _)| *modified);
+    let now = SystemTime::now();
+    for (modified, size, path) in finished {
+        let expired = now.duration_since(modified).map(|age| age > MAX_AGE).unwrap_or(false);
+        if !expired && total <= MAX_TOTAL_BYTES {
+            break;
+        }
+        match fs::remove_dir_all(&path) {
+            Ok(_) => {
+                total -= size;
+                println!("清理会话日志: {}", path.display());
+            }
+            Err(e) => println!("清理会话日志失败: {}", e),
+        }
+    }
+}
+
+fn open_session(id: &str) -> Result<SessionLog, String> {
+    let dir = session_dir(id)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut log = SessionLog {
+        raw: RotatingFile::open(dir.join("raw.log"))?,
+        text: RotatingFile::open(dir.join("text.log"))?,
+        stripper: AnsiStripper::default(),
+    };
+    let marker = format!("\n===== 会话开始 {} =====\n", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+    log.text.write(marker.as_bytes());
+    Ok(log)
+}
+
+// 追加一段终端输出：raw 为收到的原始字节，text 为解码后的文本；会话首次输出时打开日志文件
+pub fn append(id: &str, raw: &[u8], text: &str) {
+    let existing = SESSION_LOGS.lock().get(id).cloned();
+    let log = match existing {
+        Some(log) => log,
+        None => {
+            let log = match open_session(id) {
+                Ok(log) => Arc::new(Mutex::new(log)),
+                Err(e) => {
+                    println!("打开会话日志失败: {}", e);
+                    return;
+                }
+            };
+            let log = SESSION_LOGS.lock().entry(id.to_string()).or_insert(log).clone();
+            // 新会话开始时清理旧日志
+            spawn_prune();
+            log
+        }
+    };
+    let mut log = log.lock();
+    if !raw.is_empty() {
+        log.raw.write(raw);
+    }
+    let text = log.stripper.strip(text);
+    if !text.is_empty() {
+        log.text.write(text.as_bytes());
+    }
+}
+
+// 会话结束时关闭日志
+pub fn close(id: &str) {
+    let removed = SESSION_LOGS.lock().remove(id);
+    if let Some(log) = removed {
+        log.lock().flush();
+    }
+}
+
+// 把进行中会话的缓冲写入磁盘
+fn flush(id: &str) {
+    let log = SESSION_LOGS.lock().get(id).cloned();
+    if let Some(log) = log {
+        log.lock().flush();
+    }
+}
+
+// 搜索结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogMatch {
+    pub file: String,
+    pub line_number: usize,
+    pub line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+// 会话日志信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLogInfo {
+    pub id: String,
+    pub path: String,
+    pub size: u64,
+    pub active: bool,
+}
+
+// 搜索会话的文本日志（从最旧的轮转文件到当前文件），返回匹配行及上下文
+#[tauri::command]
+pub fn search_session_log(
+    id: String,
+    query: String,
+    case_sensitive: Option<bool>,
+    context: Option<usize>,
+    max_results: Option<usize>,
+) -> Result<Vec<LogMatch>, String> {
+    if query.is_empty() {
+        return Err("搜索内容不能为空".into());
+    }
+    let case_sensitive = case_sensitive.unwrap_or(false);
+    let context = context.unwrap_or(2);
+    let max_results = max_results.unwrap_or(500);
+
+    flush(&id);
+    let current = session_dir(&id)?.join("text.log");
+    let mut files: Vec<PathBuf> = (1..=MAX_ROTATED_FILES).rev().map(|i| rotated_path(&current, i)).collect();
+    files.push(current);
+    search_files(&files, &query, case_sensitive, context, max_results)
+}
+
+// 按顺序搜索多个文本文件；上下文只在同一文件内计算
+fn search_files(
+    files: &[PathBuf],
+    query: &str,
+    case_sensitive: bool,
+    context: usize,
+    max_results: usize,
+) -> Result<Vec<LogMatch>, String> {
+    let needle = if case_sensitive { query.to_string() } else { query.to_lowercase() };
+    let mut results: Vec<LogMatch> = Vec::new();
+    for path in files.iter().filter(|p| p.exists()) {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+        let reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+        let mut before: VecDeque<String> = VecDeque::with_capacity(context + 1);
+        // 等待补全后续上下文的匹配：(结果下标, 还需要的行数)
+        let mut pending: Vec<(usize, usize)> = Vec::new();
+        for (index, line) in reader.split(b'\n').enumerate() {
+            let line = match line {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                Err(_) => break,
+            };
+
+            pending.retain_mut(|(result, remaining)| {
+                results[*result].after.push(line.clone());
+                *remaining -= 1;
+                *remaining > 0
+            });
+
+            let haystack = if case_sensitive { line.clone() } else { line.to_lowercase() };
+            if haystack.contains(&needle) {
+                if results.len() >= max_results {
+                    if pending.is_empty() {
+                        return Ok(results);
+                    }
+                } else {
+                    results.push(LogMatch {
+                        file: file_name.clone(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        before: before.iter().cloned().collect(),
+                        after: Vec::new(),
+                    });
+                    if context > 0 {
+                        pending.push((results.len() - 1, context));
+                    }
+                }
+            }
+
+            if context > 0 {
+                if before.len() == context {
+                    before.pop_front();
+                }
+                before.push_back(line);
+            }
+        }
+    }
+    Ok(results)
+}
+
+// 列出所有会话日志
+#[tauri::command]
+pub fn list_session_logs() -> Result<Vec<SessionLogInfo>, String> {
+    let active: Vec<String> = SESSION_LOGS.lock().keys().cloned().collect();
+    let mut items = Vec::new();
+    for entry in fs::read_dir(logs_dir()?).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let id = entry.file_name().to_string_lossy().to_string();
+        let size = fs::read_dir(&path)
+            .map(|files| files.flatten().filter_map(|f| f.metadata().ok()).map(|m| m.len()).sum())
+            .unwrap_or(0);
+        items.push(SessionLogInfo {
+            active: active.iter().any(|a| session_dir(a).map(|d| d == path).unwrap_or(false)),
+            id,
+            path: path.to_string_lossy().to_string(),
+            size,
+        });
+    }
+    items.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(items)
+}
+
+// 删除会话日志（会话仍在进行时，之后的输出会重新创建日志）
+#[tauri::command]
+pub fn delete_session_log(id: String) -> Result<(), String> {
+    close(&id);
+    let dir = session_dir(&id)?;
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip_chunks(chunks: &[&str]) -> String {
+        let mut stripper = AnsiStripper::default();
+        chunks.iter().map(|chunk| stripper.strip(chunk)).collect()
+    }
+
+    #[test]
+    fn csi_split_across_chunks() {
+        assert_eq!(strip_chunks(&["a\x1b[3", "1;1", "mb"]), "ab");
+        assert_eq!(strip_chunks(&["a\x1b", "[2Jb"]), "ab");
+    }
+
+    #[test]
+    fn osc_split_across_chunks() {
+        assert_eq!(strip_chunks(&["x\x1b]0;ti", "tle\x07y"]), "xy");
+        // ESC \ 结束符被拆开
+        assert_eq!(strip_chunks(&["x\x1b]8;;http://a\x1b", "\\y"]), "xy");
+    }
+
+    #[test]
+    fn other_sequences_and_controls() {
+        assert_eq!(strip_chunks(&["\x1b(Bok\r\n\tdone\x1b="]), "ok\n\tdone");
+    }
+
+    // 在临时目录中写入测试文件
+    fn write_files(name: &str, contents: &[&str]) -> Vec<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("termlink-log-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| {
+                let path = dir.join(format!("text.{}.log", i));
+                fs::write(&path, content).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    #[test]
+    fn search_returns_context_lines() {
+        let files = write_files("context", &["one\ntwo\nMatch three\nfour\nfive\nsix\n"]);
+        let results = search_files(&files, "match", false, 2, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 3);
+        assert_eq!(results[0].before, ["one", "two"]);
+        assert_eq!(results[0].after, ["four", "five"]);
+        assert!(search_files(&files, "match", true, 2, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search_context_stays_within_file() {
+        let files = write_files("files", &["a\nhit 1\n", "hit 2\nb\n"]);
+        let results = search_files(&files, "hit", false, 2, 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].before, ["a"]);
+        assert!(results[0].after.is_empty());
+        assert_eq!(results[1].file, "text.1.log");
+        assert!(results[1].before.is_empty());
+        assert_eq!(results[1].after, ["b"]);
+    }
+
+    #[test]
+    fn search_overlapping_matches_and_limit() {
+        let files = write_files("limit", &["hit 1\nhit 2\nx\nhit 3\n"]);
+        let results = search_files(&files, "hit", false, 1, 2).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].after, ["hit 2"]);
+        assert_eq!(results[1].before, ["hit 1"]);
+        // 达到上限后仍补全最后一个结果的上下文
+        assert_eq!(results[1].after, ["x"]);
+        let results = search_files(&files, "hit", false, 0, 10).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.before.is_empty() && r.after.is_empty()));
+    }
+}
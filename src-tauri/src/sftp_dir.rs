@@ -0,0 +1,829 @@
+// SFTP 目录传输：递归上传/下载目录，支持 glob 过滤、符号链接策略、保留权限和修改时间，逐文件记录错误
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::FileAttributes;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use crate::sftp_russh::{ChunkedTransfer, TransferEnd};
+
+// 聚合进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+// 跟随符号链接时的最大目录深度（防止链接成环）
+const MAX_DEPTH: usize = 64;
+
+// 符号链接处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    // 传输链接指向的文件或目录
+    Follow,
+    #[default]
+    Skip,
+    // 在目标端创建相同的符号链接
+    Preserve,
+}
+
+// 目录传输选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirTransferOptions {
+    // 只传输匹配任一模式的文件（为空时传输全部）；不含 / 的模式匹配文件名，否则匹配相对路径
+    pub include: Vec<String>,
+    // 跳过匹配任一模式的文件和目录
+    pub exclude: Vec<String>,
+    pub symlinks: SymlinkPolicy,
+    pub preserve_permissions: bool,
+    pub preserve_times: bool,
+}
+
+impl Default for DirTransferOptions {
+    fn default() -> Self {
+        DirTransferOptions {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            symlinks: SymlinkPolicy::Skip,
+            preserve_permissions: true,
+            preserve_times: true,
+        }
+    }
+}
+
+// glob 匹配：* 和 ? 不跨越 /，** 匹配任意层级
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            // "**/" 也可以匹配零层目录
+            let rest = &pattern[2..];
+            let rest_no_slash = rest.strip_prefix(&['/'][..]).unwrap_or(rest);
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+                || glob_match(rest_no_slash, text)
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !text.is_empty() && text[0] != '/' && glob_match(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+// 模式是否匹配相对路径（/ 分隔）
+fn pattern_matches(pattern: &str, relative: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let text: Vec<char> = if pattern.contains('/') {
+        relative.chars().collect()
+    } else {
+        relative.rsplit('/').next().unwrap_or(relative).chars().collect()
+    };
+    let pattern: Vec<char> = pattern.trim_start_matches('/').chars().collect();
+    glob_match(&pattern, &text)
+}
+
+impl DirTransferOptions {
+    pub fn is_excluded(&self, relative: &str) -> bool {
+        self.exclude.iter().any(|p| pattern_matches(p, relative))
+    }
+
+    pub fn is_included(&self, relative: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, relative))
+    }
+}
+
+// 目录树中的条目
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryKind {
+    Dir,
+    File,
+    Symlink(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeEntry {
+    // 相对于传输根目录的路径，/ 分隔
+    pub relative: String,
+    pub kind: EntryKind,
+    pub size: u64,
+    pub mode: Option<u32>,
+    pub mtime: Option<u64>,
+}
+
+// 逐文件的错误记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileError {
+    pub path: String,
+    pub error: String,
+}
+
+// 目录传输结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirTransferReport {
+    pub files: usize,
+    pub directories: usize,
+    pub symlinks: usize,
+    pub skipped: usize,
+    pub bytes: u64,
+    pub errors: Vec<FileError>,
+    pub cancelled: bool,
+}
+
+// 聚合进度（dir-transfer-progress 事件）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirTransferProgress {
+    transfer_id: u32,
+    files_done: usize,
+    files_total: usize,
+    bytes_done: u64,
+    bytes_total: u64,
+    current_file: String,
+    progress: u32,
+}
+
+// 进行中目录传输的取消标志
+static DIR_TRANSFERS: Lazy<Mutex<HashMap<u32, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn join_relative(base: &str, relative: &str) -> String {
+    if relative.is_empty() {
+        base.to_string()
+    } else if base.ends_with('/') {
+        format!("{}{}", base, relative)
+    } else {
+        format!("{}/{}", base, relative)
+    }
+}
+
+// 单个文件名不能是 . / ..，也不能包含路径分隔符或 NUL
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+// 相对路径对应的本地路径，确保结果位于 root 之内
+pub fn local_target(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    if relative.is_empty() || Path::new(relative).is_absolute() || !relative.split('/').all(is_safe_name) {
+        return Err(format!("不安全的路径: {}", relative));
+    }
+    let path = root.join(relative);
+    if !path.starts_with(root) {
+        return Err(format!("路径超出目标目录: {}", relative));
+    }
+    Ok(path)
+}
+
+fn system_time_secs(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs())
+}
+
+#[cfg(unix)]
+fn local_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn local_mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+// 本地目录遍历的状态
+struct LocalWalk<'a> {
+    options: &'a DirTransferOptions,
+    cancelled: &'a AtomicBool,
+    visited: HashSet<PathBuf>,
+    entries: Vec<TreeEntry>,
+    skipped: &'a mut usize,
+}
+
+// 遍历本地目录（先序：目录在其内容之前）；取消时停止遍历，返回已遍历的部分
+pub fn walk_local(
+    root: &Path,
+    options: &DirTransferOptions,
+    cancelled: &AtomicBool,
+    skipped: &mut usize,
+) -> Result<Vec<TreeEntry>, String> {
+    let mut walk = LocalWalk { options, cancelled, visited: HashSet::new(), entries: Vec::new(), skipped };
+    if let Ok(canonical) = root.canonicalize() {
+        walk.visited.insert(canonical);
+    }
+    walk_local_dir(&mut walk, root, "", 0)?;
+    Ok(walk.entries)
+}
+
+fn walk_local_dir(walk: &mut LocalWalk<'_>, dir: &Path, prefix: &str, depth: usize) -> Result<(), String> {
+    if walk.cancelled.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let options = walk.options;
+    let mut children: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| format!("读取目录 {} 失败: {}", dir.display(), e))?
+        .flatten()
+        .collect();
+    children.sort_by_key(|c| c.file_name());
+    for child in children {
+        let name = child.file_name().to_string_lossy().to_string();
+        let relative = join_relative(prefix, &name).trim_start_matches('/').to_string();
+        if options.is_excluded(&relative) {
+            *walk.skipped += 1;
+            continue;
+        }
+        let path = child.path();
+        let mut metadata = match std::fs::symlink_metadata(&path) {
+            Ok(m) => m,
+            Err(_) => {
+                *walk.skipped += 1;
+                continue;
+            }
+        };
+        if metadata.file_type().is_symlink() {
+            match options.symlinks {
+                SymlinkPolicy::Skip => {
+                    *walk.skipped += 1;
+                    continue;
+                }
+                SymlinkPolicy::Preserve => {
+                    let target = std::fs::read_link(&path).map(|t| t.to_string_lossy().to_string()).unwrap_or_default();
+                    if options.is_included(&relative) {
+                        walk.entries.push(TreeEntry { relative, kind: EntryKind::Symlink(target), size: 0, mode: None, mtime: None });
+                    }
+                    continue;
+                }
+                SymlinkPolicy::Follow => {
+                    metadata = match std::fs::metadata(&path) {
+                        Ok(m) => m,
+                        // 悬空链接
+                        Err(_) => {
+                            *walk.skipped += 1;
+                            continue;
+                        }
+                    };
+                }
+            }
+        }
+        let mtime = system_time_secs(metadata.modified());
+        if metadata.is_dir() {
+            // 跟随链接时避免重复进入同一目录
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if depth >= MAX_DEPTH || !walk.visited.insert(canonical) {
+                *walk.skipped += 1;
+                continue;
+            }
+            walk.entries.push(TreeEntry { relative: relative.clone(), kind: EntryKind::Dir, size: 0, mode: local_mode(&metadata), mtime });
+            walk_local_dir(walk, &path, &relative, depth + 1)?;
+        } else if options.is_included(&relative) {
+            walk.entries.push(TreeEntry { relative, kind: EntryKind::File, size: metadata.len(), mode: local_mode(&metadata), mtime });
+        } else {
+            *walk.skipped += 1;
+        }
+    }
+    Ok(())
+}
+
+// 遍历远程目录（先序：目录在其内容之前）；取消时停止遍历，返回已遍历的部分
+// 服务器返回的文件名含路径分隔符等时跳过，防止下载时写到目标目录之外
+pub async fn walk_remote(
+    session: &SftpSession,
+    root: &str,
+    options: &DirTransferOptions,
+    cancelled: &AtomicBool,
+    skipped: &mut usize,
+) -> Result<Vec<TreeEntry>, String> {
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    let root_real = session.canonicalize(root).await.unwrap_or_else(|_| root.to_string());
+    visited.insert(root_real);
+    // 用栈代替递归；子目录的内容紧跟在该目录之后
+    let mut stack: Vec<(String, usize)> = vec![(String::new(), 0)];
+    while let Some((prefix, depth)) = stack.pop() {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        let dir = join_relative(root, &prefix);
+        let mut children: Vec<_> = session
+            .read_dir(&dir)
+            .await
+            .map_err(|e| format!("读取目录 {} 失败: {}", dir, e))?
+            .collect();
+        children.sort_by_key(|c| c.file_name());
+        let mut subdirs = Vec::new();
+        for child in children {
+            let name = child.file_name();
+            if name == "." || name == ".." {
+                continue;
+            }
+            if !is_safe_name(&name) {
+                println!("跳过不安全的远程文件名: {:?}", name);
+                *skipped += 1;
+                continue;
+            }
+            let relative = join_relative(&prefix, &name).trim_start_matches('/').to_string();
+            if options.is_excluded(&relative) {
+                *skipped += 1;
+                continue;
+            }
+            let path = join_relative(root, &relative);
+            let mut metadata = child.metadata();
+            if child.file_type().is_symlink() {
+                match options.symlinks {
+                    SymlinkPolicy::Skip => {
+                        *skipped += 1;
+                        continue;
+                    }
+                    SymlinkPolicy::Preserve => {
+                        let target = session.read_link(&path).await.unwrap_or_default();
+                        if options.is_included(&relative) {
+                            entries.push(TreeEntry { relative, kind: EntryKind::Symlink(target), size: 0, mode: None, mtime: None });
+                        }
+                        continue;
+                    }
+                    SymlinkPolicy::Follow => {
+                        metadata = match session.metadata(&path).await {
+                            Ok(m) => m,
+                            Err(_) => {
+                                *skipped += 1;
+                                continue;
+                            }
+                        };
+                    }
+                }
+            }
+            let mode = metadata.permissions.map(|p| p & 0o7777);
+            let mtime = metadata.mtime.map(|t| t as u64);
+            if metadata.is_dir() {
+                let real = session.canonicalize(&path).await.unwrap_or_else(|_| path.clone());
+                if depth >= MAX_DEPTH || !visited.insert(real) {
+                    *skipped += 1;
+                    continue;
+                }
+                entries.push(TreeEntry { relative: relative.clone(), kind: EntryKind::Dir, size: 0, mode, mtime });
+                subdirs.push((relative, depth + 1));
+            } else if options.is_included(&relative) {
+                entries.push(TreeEntry { relative, kind: EntryKind::File, size: metadata.len(), mode, mtime });
+            } else {
+                *skipped += 1;
+            }
+        }
+        // 逆序入栈，按名称顺序处理
+        stack.extend(subdirs.into_iter().rev());
+    }
+    Ok(entries)
+}
+
+// 以只修改属性的方式打开文件或目录，只读文件也可以设置修改时间
+fn open_for_times(path: &Path) -> std::io::Result<std::fs::File> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+        // 打开目录需要 FILE_FLAG_BACKUP_SEMANTICS
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        std::fs::File::options()
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)
+    }
+    #[cfg(not(windows))]
+    std::fs::File::open(path)
+}
+
+// 设置本地文件的修改时间和权限（先设置时间，权限最后设置，以免只读权限导致设置时间失败）
+fn apply_local_attributes(path: &Path, entry: &TreeEntry, options: &DirTransferOptions) -> Result<(), String> {
+    if options.preserve_times {
+        if let Some(mtime) = entry.mtime {
+            open_for_times(path)
+                .and_then(|file| file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime)))
+                .map_err(|e| format!("设置修改时间失败: {}", e))?;
+        }
+    }
+    #[cfg(unix)]
+    if options.preserve_permissions {
+        if let Some(mode) = entry.mode {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                .map_err(|e| format!("设置权限失败: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// 设置远程文件的权限和修改时间
+async fn apply_remote_attributes(
+    session: &SftpSession,
+    path: &str,
+    entry: &TreeEntry,
+    options: &DirTransferOptions,
+) -> Result<(), String> {
+    // 只包含要修改的字段，其余字段为空时服务器不做修改
+    let mut attributes = FileAttributes::empty();
+    let mut changed = false;
+    if options.preserve_permissions && entry.mode.is_some() {
+        attributes.permissions = entry.mode;
+        changed = true;
+    }
+    if options.preserve_times {
+        if let Some(mtime) = entry.mtime {
+            attributes.atime = Some(mtime as u32);
+            attributes.mtime = Some(mtime as u32);
+            changed = true;
+        }
+    }
+    if changed {
+        session
+            .set_metadata(path, attributes)
+            .await
+            .map_err(|e| format!("设置远程文件属性失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// 传输过程中的聚合进度
+struct ProgressTracker<'a> {
+    app: &'a tauri::AppHandle,
+    transfer_id: u32,
+    files_done: usize,
+    files_total: usize,
+    bytes_done: u64,
+    bytes_total: u64,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressTracker<'_> {
+    fn emit(&mut self, current_file: &str, file_bytes: u64, force: bool) {
+        if !force && self.last_emit.map(|t| t.elapsed() < PROGRESS_INTERVAL).unwrap_or(false) {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        let bytes_done = self.bytes_done + file_bytes;
+        let progress = if self.bytes_total > 0 {
+            ((bytes_done as f64 / self.bytes_total as f64) * 100.0) as u32
+        } else {
+            100
+        };
+        let _ = self.app.emit("dir-transfer-progress", DirTransferProgress {
+            transfer_id: self.transfer_id,
+            files_done: self.files_done,
+            files_total: self.files_total,
+            bytes_done,
+            bytes_total: self.bytes_total,
+            current_file: current_file.to_string(),
+            progress,
+        });
+    }
+}
+
+// 登记目录传输（目录同步也使用），返回取消标志
+pub fn register(transfer_id: u32) -> Arc<AtomicBool> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    DIR_TRANSFERS.lock().insert(transfer_id, cancelled.clone());
+    cancelled
+}
+
+pub fn unregister(transfer_id: u32) {
+    DIR_TRANSFERS.lock().remove(&transfer_id);
+}
+
+// 一次目录传输（或目录同步）的上下文
+pub struct DirTransfer<'a> {
+    pub app: &'a tauri::AppHandle,
+    pub session: &'a SftpSession,
+    pub transfer_id: u32,
+    pub options: &'a DirTransferOptions,
+    pub cancelled: &'a AtomicBool,
+}
+
+// 递归上传本地目录到远程
+#[tauri::command]
+pub async fn upload_sftp_directory(
+    app: tauri::AppHandle,
+    connection_id: String,
+    local_path: String,
+    remote_path: String,
+    transfer_id: u32,
+    options: Option<DirTransferOptions>,
+) -> Result<DirTransferReport, String> {
+    let session = crate::sftp_russh::sftp_session(&connection_id)?;
+    let options = options.unwrap_or_default();
+    println!("上传目录: {} -> {}", local_path, remote_path);
+
+    let mut report = DirTransferReport::default();
+    // 遍历前登记，遍历大目录时也可以取消
+    let cancelled = register(transfer_id);
+    let result = match walk_local(Path::new(&local_path), &options, &cancelled, &mut report.skipped) {
+        Ok(_) if cancelled.load(Ordering::Relaxed) => {
+            report.cancelled = true;
+            Ok(())
+        }
+        Ok(entries) => {
+            let context = DirTransfer { app: &app, session: &session, transfer_id, options: &options, cancelled: &cancelled };
+            upload_entries(&context, &local_path, &remote_path, &entries, &mut report).await
+        }
+        Err(e) => Err(e),
+    };
+    unregister(transfer_id);
+    result.map(|_| report)
+}
+
+// 按条目列表上传（目录同步也使用）
+pub async fn upload_entries(
+    context: &DirTransfer<'_>,
+    local_root: &str,
+    remote_root: &str,
+    entries: &[TreeEntry],
+    report: &mut DirTransferReport,
+) -> Result<(), String> {
+    let DirTransfer { app, session, transfer_id, options, cancelled } = *context;
+    if !session.try_exists(remote_root).await.unwrap_or(false) {
+        session
+            .create_dir(remote_root)
+            .await
+            .map_err(|e| format!("创建远程目录 {} 失败: {}", remote_root, e))?;
+    }
+
+    let mut tracker = ProgressTracker {
+        app,
+        transfer_id,
+        files_done: 0,
+        files_total: entries.iter().filter(|e| e.kind == EntryKind::File).count(),
+        bytes_done: 0,
+        bytes_total: entries.iter().map(|e| e.size).sum(),
+        last_emit: None,
+    };
+    tracker.emit("", 0, true);
+    // 创建失败的目录，其内容不再尝试
+    let mut failed_dirs: Vec<String> = Vec::new();
+
+    for entry in entries {
+        if cancelled.load(Ordering::Relaxed) {
+            report.cancelled = true;
+            break;
+        }
+        if failed_dirs.iter().any(|d| entry.relative.starts_with(&format!("{}/", d))) {
+            report.skipped += 1;
+            continue;
+        }
+        let local = Path::new(local_root).join(&entry.relative);
+        let remote = join_relative(remote_root, &entry.relative);
+        let result = match &entry.kind {
+            EntryKind::Dir => {
+                let created = if session.try_exists(&remote).await.unwrap_or(false) {
+                    Ok(())
+                } else {
+                    session.create_dir(&remote).await.map_err(|e| format!("创建远程目录失败: {}", e))
+                };
+                if created.is_err() {
+                    failed_dirs.push(entry.relative.clone());
+                } else {
+                    report.directories += 1;
+                }
+                created
+            }
+            EntryKind::Symlink(target) => {
+                let _ = session.remove_file(&remote).await;
+                session
+                    .symlink(&remote, target)
+                    .await
+                    .map(|_| report.symlinks += 1)
+                    .map_err(|e| format!("创建符号链接失败: {}", e))
+            }
+            EntryKind::File => {
+                let transfer = ChunkedTransfer {
+                    app,
+                    session,
+                    progress_id: None,
+                    offset: 0,
+                    on_progress: |done, _| tracker.emit(&entry.relative, done, false),
+                    should_stop: || cancelled.load(Ordering::Relaxed),
+                };
+                let file_result = crate::sftp_russh::upload_chunked(transfer, &local.to_string_lossy(), &remote).await;
+                match file_result {
+                    Ok(TransferEnd::Completed) => {
+                        tracker.files_done += 1;
+                        tracker.bytes_done += entry.size;
+                        report.files += 1;
+                        report.bytes += entry.size;
+                        apply_remote_attributes(session, &remote, entry, options).await
+                    }
+                    Ok(TransferEnd::Stopped) => {
+                        let _ = session.remove_file(&remote).await;
+                        report.cancelled = true;
+                        break;
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(error) = result {
+            println!("上传 {} 失败: {}", entry.relative, error);
+            report.errors.push(FileError { path: entry.relative.clone(), error });
+        }
+    }
+
+    // 目录的修改时间在其内容写入后设置（由深到浅）
+    for entry in entries.iter().rev().filter(|e| e.kind == EntryKind::Dir) {
+        if report.cancelled || failed_dirs.contains(&entry.relative) {
+            continue;
+        }
+        let remote = join_relative(remote_root, &entry.relative);
+        if let Err(error) = apply_remote_attributes(session, &remote, entry, options).await {
+            report.errors.push(FileError { path: entry.relative.clone(), error });
+        }
+    }
+    tracker.emit("", 0, true);
+    Ok(())
+}
+
+// 递归下载远程目录到本地
+#[tauri::command]
+pub async fn download_sftp_directory(
+    app: tauri::AppHandle,
+    connection_id: String,
+    remote_path: String,
+    local_path: String,
+    transfer_id: u32,
+    options: Option<DirTransferOptions>,
+) -> Result<DirTransferReport, String> {
+    let session = crate::sftp_russh::sftp_session(&connection_id)?;
+    let options = options.unwrap_or_default();
+    println!("下载目录: {} -> {}", remote_path, local_path);
+
+    let mut report = DirTransferReport::default();
+    // 遍历前登记，遍历大目录时也可以取消
+    let cancelled = register(transfer_id);
+    let result = match walk_remote(&session, &remote_path, &options, &cancelled, &mut report.skipped).await {
+        Ok(_) if cancelled.load(Ordering::Relaxed) => {
+            report.cancelled = true;
+            Ok(())
+        }
+        Ok(entries) => {
+            let context = DirTransfer { app: &app, session: &session, transfer_id, options: &options, cancelled: &cancelled };
+            download_entries(&context, &remote_path, &local_path, &entries, &mut report).await
+        }
+        Err(e) => Err(e),
+    };
+    unregister(transfer_id);
+    result.map(|_| report)
+}
+
+// 按条目列表下载（目录同步也使用）
+pub async fn download_entries(
+    context: &DirTransfer<'_>,
+    remote_root: &str,
+    local_root: &str,
+    entries: &[TreeEntry],
+    report: &mut DirTransferReport,
+) -> Result<(), String> {
+    let DirTransfer { app, session, transfer_id, options, cancelled } = *context;
+    std::fs::create_dir_all(local_root).map_err(|e| format!("创建本地目录 {} 失败: {}", local_root, e))?;
+
+    let mut tracker = ProgressTracker {
+        app,
+        transfer_id,
+        files_done: 0,
+        files_total: entries.iter().filter(|e| e.kind == EntryKind::File).count(),
+        bytes_done: 0,
+        bytes_total: entries.iter().map(|e| e.size).sum(),
+        last_emit: None,
+    };
+    tracker.emit("", 0, true);
+    let mut failed_dirs: Vec<String> = Vec::new();
+
+    for entry in entries {
+        if cancelled.load(Ordering::Relaxed) {
+            report.cancelled = true;
+            break;
+        }
+        if failed_dirs.iter().any(|d| entry.relative.starts_with(&format!("{}/", d))) {
+            report.skipped += 1;
+            continue;
+        }
+        let local = match local_target(Path::new(local_root), &entry.relative) {
+            Ok(local) => local,
+            Err(error) => {
+                report.skipped += 1;
+                report.errors.push(FileError { path: entry.relative.clone(), error });
+                continue;
+            }
+        };
+        let remote = join_relative(remote_root, &entry.relative);
+        let result = match &entry.kind {
+            EntryKind::Dir => match std::fs::create_dir_all(&local) {
+                Ok(_) => {
+                    report.directories += 1;
+                    Ok(())
+                }
+                Err(e) => {
+                    failed_dirs.push(entry.relative.clone());
+                    Err(format!("创建本地目录失败: {}", e))
+                }
+            },
+            EntryKind::Symlink(target) => create_local_symlink(target, &local).map(|_| report.symlinks += 1),
+            EntryKind::File => {
+                let local_str = local.to_string_lossy().to_string();
+                let transfer = ChunkedTransfer {
+                    app,
+                    session,
+                    progress_id: None,
+                    offset: 0,
+                    on_progress: |done, _| tracker.emit(&entry.relative, done, false),
+                    should_stop: || cancelled.load(Ordering::Relaxed),
+                };
+                let file_result = crate::sftp_russh::download_chunked(transfer, &remote, &local_str).await;
+                match file_result {
+                    Ok(TransferEnd::Completed) => {
+                        tracker.files_done += 1;
+                        tracker.bytes_done += entry.size;
+                        report.files += 1;
+                        report.bytes += entry.size;
+                        apply_local_attributes(&local, entry, options)
+                    }
+                    Ok(TransferEnd::Stopped) => {
+                        let _ = std::fs::remove_file(&local);
+                        report.cancelled = true;
+                        break;
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(error) = result {
+            println!("下载 {} 失败: {}", entry.relative, error);
+            report.errors.push(FileError { path: entry.relative.clone(), error });
+        }
+    }
+
+    for entry in entries.iter().rev().filter(|e| e.kind == EntryKind::Dir) {
+        if report.cancelled || failed_dirs.contains(&entry.relative) {
+            continue;
+        }
+        let local = match local_target(Path::new(local_root), &entry.relative) {
+            Ok(local) => local,
+            Err(_) => continue,
+        };
+        if let Err(error) = apply_local_attributes(&local, entry, options) {
+            report.errors.push(FileError { path: entry.relative.clone(), error });
+        }
+    }
+    tracker.emit("", 0, true);
+    Ok(())
+}
+
+fn create_local_symlink(target: &str, link: &Path) -> Result<(), String> {
+    let _ = std::fs::remove_file(link);
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    let result = std::os::windows::fs::symlink_file(target, link);
+    result.map_err(|e| format!("创建符号链接失败: {}", e))
+}
+
+// 取消目录传输：当前文件传输停止并删除，已完成的文件保留
+#[tauri::command]
+pub async fn cancel_dir_transfer(transfer_id: u32) -> Result<(), String> {
+    match DIR_TRANSFERS.lock().get(&transfer_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            println!("取消目录传输: {}", transfer_id);
+            Ok(())
+        }
+        None => Err("目录传输不存在".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsafe_remote_names_are_rejected() {
+        assert!(is_safe_name("report.txt"));
+        assert!(is_safe_name("..hidden"));
+        for name in ["", ".", "..", "../evil", "a/b", "/etc/passwd", "..\\evil", "C:\\evil", "a\0b"] {
+            assert!(!is_safe_name(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn local_target_stays_under_root() {
+        let root = Path::new("/tmp/download");
+        assert_eq!(local_target(root, "a/b.txt").unwrap(), root.join("a").join("b.txt"));
+        for relative in ["", "../evil", "a/../../evil", "/etc/passwd", "a//b", "a/./b", "a\\..\\..\\evil"] {
+            assert!(local_target(root, relative).is_err(), "{:?}", relative);
+        }
+    }
+}
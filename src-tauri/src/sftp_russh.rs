@@ -3,9 +3,11 @@ use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use russh::*;
-use russh_keys::*;
+use futures::stream::{FuturesUnordered, StreamExt};
+use russh_sftp::client::fs::File;
 use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags};
+use crate::ssh_session::{self, ConnectRequest};
 use tokio::io::AsyncWriteExt;
 use tauri::Emitter;
 
@@ -22,104 +24,57 @@ pub struct SftpFileInfo {
 // SFTP连接管理
 struct SftpConnection {
     session: Arc<SftpSession>,
+    // 共享SSH连接的键（profile:{id} 或 user@host:port），应用重启后仍然有效
+    key: String,
 }
 
 static SFTP_CONNECTIONS: Lazy<Mutex<HashMap<String, SftpConnection>>> = 
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-// SSH客户端处理器
-struct SftpClient;
-
-#[async_trait::async_trait]
-impl client::Handler for SftpClient {
-    type Error = russh::Error;
-
-    async fn check_server_key(
-        self,
-        _server_public_key: &key::PublicKey,
-    ) -> Result<(Self, bool), Self::Error> {
-        // 接受所有服务器密钥 (在生产环境中应该验证)
-        Ok((self, true))
-    }
-}
-
-// 连接到SFTP服务器
+// 连接到SFTP服务器（复用同一配置的共享SSH连接）
 #[tauri::command]
 pub async fn connect_sftp(
+    app: tauri::AppHandle,
     connection_id: String, 
     host: String, 
     port: u16, 
     username: String, 
-    password: Option<String>
+    password: Option<String>,
+    private_key: Option<String>,
+    passphrase: Option<String>,
+    profile_id: Option<String>,
 ) -> Result<(), String> {
     println!("连接SFTP服务器: {}@{}:{}", username, host, port);
     
-    // 创建客户端配置
-    let config = client::Config {
-        inactivity_timeout: Some(std::time::Duration::from_secs(300)),
-        ..<_>::default()
-    };
-    
-    println!("连接到SSH服务器...");
-    
-    // 连接到服务器
-    let mut session = match client::connect(Arc::new(config), (&host[..], port), SftpClient).await {
-        Ok(session) => {
-            println!("✓ SSH连接成功");
-            session
-        },
-        Err(e) => {
-            return Err(format!("SSH连接失败: {}", e));
-        }
-    };
+    let consumer = ssh_session::sftp_consumer(&connection_id);
+    let auth = crate::ssh_auth::AuthOptions::resolve(
+        &app,
+        &username,
+        password,
+        private_key,
+        passphrase,
+        profile_id.as_deref(),
+    )
+    .with_shared_prompts(&app, &consumer);
+    let request = ConnectRequest { host, port, auth, profile_id };
     
-    // 进行认证
-    if let Some(pwd) = password {
-        println!("开始密码认证...");
-        match session.authenticate_password(&username, &pwd).await {
-            Ok(true) => {
-                println!("✓ 密码认证成功");
-            },
-            Ok(false) => {
-                return Err("密码认证失败：用户名或密码错误".to_string());
-            },
-            Err(e) => {
-                return Err(format!("密码认证过程失败: {}", e));
-            }
-        }
-    } else {
-        return Err("当前仅支持密码认证".to_string());
-    }
+    let connection = ssh_session::acquire(&app, &consumer, request).await?;
     
     println!("创建SFTP通道...");
     
-    // 创建SFTP通道
-    let channel = match session.channel_open_session().await {
-        Ok(channel) => channel,
-        Err(e) => {
-            return Err(format!("创建通道失败: {}", e));
-        }
-    };
-    
-    // 请求SFTP子系统
-    if let Err(e) = channel.request_subsystem(true, "sftp").await {
-        return Err(format!("请求SFTP子系统失败: {}", e));
-    }
-    
-    // 创建SFTP会话
-    let sftp_session = match SftpSession::new(channel.into_stream()).await {
-        Ok(session) => {
-            println!("✓ SFTP会话创建成功");
-            session
-        },
+    let sftp_session = match connection.open_sftp().await {
+        Ok(session) => session,
         Err(e) => {
-            return Err(format!("创建SFTP会话失败: {}", e));
+            ssh_session::release(&consumer).await;
+            return Err(e);
         }
     };
+    println!("✓ SFTP会话创建成功");
     
     // 保存连接
     let connection = SftpConnection {
         session: Arc::new(sftp_session),
+        key: connection.key.clone(),
     };
     
     SFTP_CONNECTIONS.lock().insert(connection_id, connection);
@@ -129,9 +84,10 @@ pub async fn connect_sftp(
 
 // 断开SFTP连接
 #[tauri::command]
-pub fn disconnect_sftp(connection_id: String) -> Result<(), String> {
-    let mut connections = SFTP_CONNECTIONS.lock();
-    if let Some(_connection) = connections.remove(&connection_id) {
+pub async fn disconnect_sftp(connection_id: String) -> Result<(), String> {
+    let removed = SFTP_CONNECTIONS.lock().remove(&connection_id);
+    if let Some(_connection) = removed {
+        ssh_session::release(&ssh_session::sftp_consumer(&connection_id)).await;
         println!("SFTP连接已断开: {}", connection_id);
         Ok(())
     } else {
@@ -205,64 +161,170 @@ pub async fn list_sftp_files(connection_id: String, path: String) -> Result<Vec<
         }
 }
 
-// 下载SFTP文件（带真实进度）
+// 获取SFTP连接的会话
+pub fn sftp_session(connection_id: &str) -> Result<Arc<SftpSession>, String> {
+    match SFTP_CONNECTIONS.lock().get(connection_id) {
+        Some(conn) => Ok(conn.session.clone()),
+        None => Err("SFTP连接不存在".to_string()),
+    }
+}
+
+// SFTP连接对应的共享SSH连接键
+pub fn connection_key(connection_id: &str) -> Option<String> {
+    SFTP_CONNECTIONS.lock().get(connection_id).map(|conn| conn.key.clone())
+}
+
+// 查找连接到同一服务器（连接键相同）的在线SFTP连接
+pub fn find_connection(key: &str) -> Option<String> {
+    SFTP_CONNECTIONS
+        .lock()
+        .iter()
+        .find(|(_, conn)| conn.key == key)
+        .map(|(id, _)| id.clone())
+}
+
+// 按连接键重新打开SFTP连接（恢复应用重启前的传输），凭据从SSH配置和钥匙串读取；返回连接ID
+pub async fn open_connection(app: &tauri::AppHandle, key: &str) -> Result<String, String> {
+    let connection_id = format!("transfer:{}", key);
+    if sftp_session(&connection_id).is_ok() {
+        return Ok(connection_id);
+    }
+    let (host, port, username, password, profile_id) = match key.strip_prefix("profile:") {
+        Some(id) => {
+            let profile = crate::ssh::load_ssh_profile(app, id)?;
+            let password = crate::ssh::get_ssh_password(id.to_string()).ok().flatten();
+            (profile.host, profile.port, profile.username, password, Some(id.to_string()))
+        }
+        None => {
+            let (username, address) = key.rsplit_once('@').ok_or("无效的连接")?;
+            let (host, port) = address.rsplit_once(':').ok_or("无效的连接")?;
+            let port = port.parse().map_err(|_| "无效的连接".to_string())?;
+            (host.to_string(), port, username.to_string(), None, None)
+        }
+    };
+    connect_sftp(app.clone(), connection_id.clone(), host, port, username, password, None, None, profile_id).await?;
+    Ok(connection_id)
+}
+
+// 下载SFTP文件，通过下载管理器排队执行并等待完成
+// 需要传输ID（匹配进度、取消）时改用 download_sftp_file_with_progress + wait_transfer
 #[tauri::command]
 pub async fn download_sftp_file(
     app: tauri::AppHandle,
     connection_id: String, 
     remote_path: String, 
-    local_path: String,
-    download_id: u32
+    local_path: String
 ) -> Result<(), String> {
-    let session = {
-        let connections = SFTP_CONNECTIONS.lock();
-        let connection = match connections.get(&connection_id) {
-            Some(conn) => conn,
-            None => return Err("SFTP连接不存在".to_string()),
-        };
-        connection.session.clone()
-    }; // 锁在这里自动释放
-        
+    sftp_session(&connection_id)?;
     println!("下载文件(带进度): {} -> {}", remote_path, local_path);
+    let done = crate::download_manager::submit(&app, connection_id, remote_path, local_path, 0)?.1;
+    match done.await {
+        Ok(result) => result,
+        Err(_) => Err("下载任务已中断".to_string()),
+    }
+}
+
+// 分块传输的结束方式
+pub enum TransferEnd {
+    Completed,
+    // 被暂停或取消，本地保留已下载的部分
+    Stopped,
+}
+
+// 分块传输的上下文：从 offset 处续传，每块后调用 on_progress，should_stop 返回 true 时停止
+pub struct ChunkedTransfer<'a, P, S> {
+    pub app: &'a tauri::AppHandle,
+    pub session: &'a SftpSession,
+    // download-progress / upload-progress 事件中的传输ID；为 None 时不发送（目录传输只发送聚合进度）
+    pub progress_id: Option<u32>,
+    pub offset: u64,
+    pub on_progress: P,
+    pub should_stop: S,
+}
+
+// 分块下载远程文件
+pub async fn download_chunked<P: FnMut(u64, u64), S: Fn() -> bool>(
+    transfer: ChunkedTransfer<'_, P, S>,
+    remote_path: &str,
+    local_path: &str,
+) -> Result<TransferEnd, String> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    let ChunkedTransfer { app, session, progress_id, offset, mut on_progress, should_stop } = transfer;
     
     // 首先获取文件大小
-    let metadata = match session.metadata(&remote_path).await {
+    let metadata = match session.metadata(remote_path).await {
         Ok(meta) => meta,
         Err(e) => return Err(format!("获取文件元数据失败: {}", e)),
     };
     
     let total_size = metadata.len();
     println!("文件总大小: {} 字节", total_size);
+    // 远程文件比已下载部分还小（已被修改），从头下载
+    let offset = if offset > total_size { 0 } else { offset };
     
     // 发送初始进度
-    let _ = app.emit("download-progress", serde_json::json!({
-        "downloadId": download_id,
-        "downloaded": 0,
-        "total": total_size,
-        "progress": 0
-    }));
+    let progress_of = |downloaded: u64| if total_size > 0 {
+        ((downloaded as f64 / total_size as f64) * 100.0) as u32
+    } else {
+        0
+    };
+    let emit_progress = |downloaded: u64, progress: u32| {
+        if let Some(download_id) = progress_id {
+            let _ = app.emit("download-progress", serde_json::json!({
+                "downloadId": download_id,
+                "downloaded": downloaded,
+                "total": total_size,
+                "progress": progress
+            }));
+        }
+    };
+    emit_progress(offset, progress_of(offset));
+    on_progress(offset, total_size);
     
     // 打开远程文件进行读取
-    let mut file = match session.open(&remote_path).await {
+    let mut file = match session.open(remote_path).await {
         Ok(f) => f,
         Err(e) => return Err(format!("打开远程文件失败: {}", e)),
     };
     
-    // 创建本地文件
-    let mut local_file = match tokio::fs::File::create(&local_path).await {
-        Ok(f) => f,
-        Err(e) => return Err(format!("创建本地文件失败: {}", e)),
+    // 创建本地文件；续传时追加到已下载的部分之后
+    let mut local_file = if offset > 0 {
+        if let Err(e) = file.seek(std::io::SeekFrom::Start(offset)).await {
+            return Err(format!("定位远程文件失败: {}", e));
+        }
+        let local = tokio::fs::OpenOptions::new().write(true).open(local_path).await;
+        match local {
+            Ok(mut f) => {
+                if let Err(e) = f.set_len(offset).await {
+                    return Err(format!("截断本地文件失败: {}", e));
+                }
+                if let Err(e) = f.seek(std::io::SeekFrom::Start(offset)).await {
+                    return Err(format!("定位本地文件失败: {}", e));
+                }
+                f
+            },
+            Err(e) => return Err(format!("打开本地文件失败: {}", e)),
+        }
+    } else {
+        match tokio::fs::File::create(local_path).await {
+            Ok(f) => f,
+            Err(e) => return Err(format!("创建本地文件失败: {}", e)),
+        }
     };
     
     // 分块读取和写入
     const CHUNK_SIZE: usize = 32768; // 32KB 每块
     let mut buffer = vec![0u8; CHUNK_SIZE];
-    let mut downloaded: u64 = 0;
-    let mut last_progress_percent = 0;
+    let mut downloaded: u64 = offset;
+    let mut last_progress_percent = progress_of(offset);
+    let mut end = TransferEnd::Completed;
     
     loop {
-        // 使用 AsyncReadExt 的 read 方法读取一块数据
-        use tokio::io::AsyncReadExt;
+        if should_stop() {
+            end = TransferEnd::Stopped;
+            break;
+        }
+        
         let bytes_read = match file.read(&mut buffer).await {
             Ok(n) => n,
             Err(e) => return Err(format!("读取远程文件失败: {}", e)),
@@ -278,24 +340,16 @@ pub async fn download_sftp_file(
         }
         
         downloaded += bytes_read as u64;
+        on_progress(downloaded, total_size);
         
         // 计算进度百分比
-        let progress = if total_size > 0 {
-            ((downloaded as f64 / total_size as f64) * 100.0) as u32
-        } else {
-            0
-        };
+        let progress = progress_of(downloaded);
         
         // 只在进度变化时发送更新（避免过多事件）
         if progress != last_progress_percent || downloaded == total_size {
             last_progress_percent = progress;
             
-            let _ = app.emit("download-progress", serde_json::json!({
-                "downloadId": download_id,
-                "downloaded": downloaded,
-                "total": total_size,
-                "progress": progress
-            }));
+            emit_progress(downloaded, progress);
             
             println!("下载进度: {}/{} 字节 ({}%)", downloaded, total_size, progress);
         }
@@ -306,42 +360,262 @@ pub async fn download_sftp_file(
         return Err(format!("刷新文件缓冲失败: {}", e));
     }
     
-    println!("文件下载成功: {}", local_path);
-    Ok(())
+    if let TransferEnd::Completed = end {
+        println!("文件下载成功: {}", local_path);
+    }
+    Ok(end)
 }
 
-// 上传文件到SFTP
+// 上传文件到SFTP：通过下载管理器排队执行，分块流式上传并发送 upload-progress 事件
 #[tauri::command]
 pub async fn upload_sftp_file(
+    app: tauri::AppHandle,
     connection_id: String, 
     local_path: String, 
     remote_path: String
 ) -> Result<(), String> {
-    let session = {
-        let connections = SFTP_CONNECTIONS.lock();
-        let connection = match connections.get(&connection_id) {
-            Some(conn) => conn,
-            None => return Err("SFTP连接不存在".to_string()),
+    sftp_session(&connection_id)?;
+    println!("上传文件: {} -> {}", local_path, remote_path);
+    let done = crate::download_manager::submit_upload(&app, connection_id, local_path, remote_path, 0)?.1;
+    match done.await {
+        Ok(result) => result,
+        Err(_) => Err("上传任务已中断".to_string()),
+    }
+}
+
+// 上传分块大小，以及同时进行中的写请求数（每个请求使用一个独立的远程文件句柄）
+const UPLOAD_CHUNK_SIZE: usize = 32768;
+const UPLOAD_MAX_IN_FLIGHT: usize = 8;
+
+// 从本地文件读取一块数据（读满或到文件末尾）
+async fn read_chunk(file: &mut tokio::fs::File, size: usize) -> std::io::Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+    let mut buffer = vec![0u8; size];
+    let mut filled = 0;
+    while filled < size {
+        let n = file.read(&mut buffer[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    buffer.truncate(filled);
+    Ok(buffer)
+}
+
+// 在指定偏移写入一块数据，完成后归还文件句柄
+async fn write_chunk(mut file: File, offset: u64, data: Vec<u8>) -> Result<(File, u64, usize), String> {
+    use tokio::io::AsyncSeekExt;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("定位远程文件失败: {}", e))?;
+    file.write_all(&data).await.map_err(|e| format!("写入远程文件失败: {}", e))?;
+    Ok((file, offset, data.len()))
+}
+
+// 两端文件的大小和修改时间（秒），文件不存在时为 None
+pub async fn remote_stat(session: &SftpSession, path: &str) -> Option<(u64, Option<u64>)> {
+    let metadata = session.metadata(path).await.ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    Some((metadata.len(), modified))
+}
+
+// 计算从当前位置起 length 字节的 SHA-1
+async fn prefix_sha1(mut reader: impl tokio::io::AsyncRead + Unpin, length: u64) -> std::io::Result<Vec<u8>> {
+    use sha1::{Digest, Sha1};
+    use tokio::io::AsyncReadExt;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0u8; UPLOAD_CHUNK_SIZE];
+    let mut remaining = length;
+    while remaining > 0 {
+        let want = remaining.min(buffer.len() as u64) as usize;
+        let n = reader.read(&mut buffer[..want]).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        hasher.update(&buffer[..n]);
+        remaining -= n as u64;
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+// 服务器不能执行命令时，续传校验只比较已传输部分末尾的这些字节
+const VERIFY_WINDOW: u64 = 1024 * 1024;
+
+// 校验两端文件 [0, offset) 范围一致：优先在服务器上执行 head -c | sha1sum，与本地前缀的 SHA-1 比较；
+// 不能执行命令时通过 SFTP 只比较末尾 VERIFY_WINDOW 字节，避免重新读取整个已传输部分
+pub async fn verify_prefix(
+    connection_id: &str,
+    session: &SftpSession,
+    remote_path: &str,
+    local_path: &str,
+    offset: u64,
+) -> Result<bool, String> {
+    use tokio::io::AsyncSeekExt;
+    if let Some(connection) = ssh_session::find_connection(connection_id) {
+        let command = format!("head -c {} -- {} | sha1sum", offset, crate::sftp_sync::shell_quote(remote_path));
+        if let Ok(output) = connection.exec(&command).await {
+            if let Some(remote_hash) = output.split_whitespace().next().filter(|h| h.len() == 40) {
+                let local = tokio::fs::File::open(local_path).await.map_err(|e| format!("打开本地文件失败: {}", e))?;
+                let local_hash = prefix_sha1(local, offset).await.map_err(|e| format!("读取本地文件失败: {}", e))?;
+                let local_hash: String = local_hash.iter().map(|b| format!("{:02x}", b)).collect();
+                return Ok(local_hash == remote_hash.to_lowercase());
+            }
+        }
+    }
+    let start = offset.saturating_sub(VERIFY_WINDOW);
+    let mut local = tokio::fs::File::open(local_path).await.map_err(|e| format!("打开本地文件失败: {}", e))?;
+    local.seek(std::io::SeekFrom::Start(start)).await.map_err(|e| format!("定位本地文件失败: {}", e))?;
+    let local_hash = prefix_sha1(local, offset - start).await.map_err(|e| format!("读取本地文件失败: {}", e))?;
+    let mut remote = session.open(remote_path).await.map_err(|e| format!("打开远程文件失败: {}", e))?;
+    remote.seek(std::io::SeekFrom::Start(start)).await.map_err(|e| format!("定位远程文件失败: {}", e))?;
+    let remote_hash = prefix_sha1(remote, offset - start).await.map_err(|e| format!("读取远程文件失败: {}", e))?;
+    Ok(local_hash == remote_hash)
+}
+
+// 分块上传本地文件，多个写请求同时进行
+pub async fn upload_chunked<P: FnMut(u64, u64), S: Fn() -> bool>(
+    transfer: ChunkedTransfer<'_, P, S>,
+    local_path: &str,
+    remote_path: &str,
+) -> Result<TransferEnd, String> {
+    use tokio::io::AsyncSeekExt;
+    let ChunkedTransfer { app, session, progress_id, offset, mut on_progress, should_stop } = transfer;
+    let mut local_file = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| format!("读取本地文件失败: {}", e))?;
+    let total_size = local_file
+        .metadata()
+        .await
+        .map_err(|e| format!("读取本地文件失败: {}", e))?
+        .len();
+    println!("文件总大小: {} 字节", total_size);
+    let offset = if offset > total_size { 0 } else { offset };
+    
+    let emit_progress = |uploaded: u64| {
+        let progress = if total_size > 0 {
+            ((uploaded as f64 / total_size as f64) * 100.0) as u32
+        } else {
+            100
         };
-        connection.session.clone()
-    }; // 锁在这里自动释放
+        if let Some(upload_id) = progress_id {
+            let _ = app.emit("upload-progress", serde_json::json!({
+                "uploadId": upload_id,
+                "uploaded": uploaded,
+                "total": total_size,
+                "progress": progress
+            }));
+        }
+        progress
+    };
+    let mut last_progress_percent = emit_progress(offset);
+    on_progress(offset, total_size);
+    
+    // 第一个句柄创建（截断）远程文件；续传时把远程文件截断到 offset，去掉之后的旧数据
+    // 其余句柄以写方式打开同一文件
+    let first = if offset > 0 {
+        local_file
+            .seek(std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("定位本地文件失败: {}", e))?;
+        session
+            .set_metadata(remote_path, FileAttributes { size: Some(offset), ..FileAttributes::empty() })
+            .await
+            .map_err(|e| format!("截断远程文件失败: {}", e))?;
+        session.open_with_flags(remote_path, OpenFlags::WRITE).await
+    } else {
+        session.create(remote_path).await
+    };
+    let mut idle = vec![first.map_err(|e| format!("打开远程文件失败: {}", e))?];
+    let chunks = (total_size - offset).div_ceil(UPLOAD_CHUNK_SIZE as u64) as usize;
+    for _ in 1..UPLOAD_MAX_IN_FLIGHT.min(chunks) {
+        match session.open_with_flags(remote_path, OpenFlags::WRITE).await {
+            Ok(file) => idle.push(file),
+            Err(_) => break,
+        }
+    }
+    
+    let mut in_flight = FuturesUnordered::new();
+    // 进行中写请求的偏移，最小者之前的部分已连续写入
+    let mut pending = std::collections::BTreeSet::new();
+    let mut next_offset = offset;
+    let mut uploaded = offset;
+    let mut eof = offset == total_size;
+    let mut end = TransferEnd::Completed;
+    
+    loop {
+        // 停止时等待进行中的写请求完成，已写入的部分保留在远程
+        if !eof && should_stop() {
+            eof = true;
+            end = TransferEnd::Stopped;
+        }
         
-        println!("上传文件: {} -> {}", local_path, remote_path);
+        // 为空闲句柄分配下一块数据
+        while !eof {
+            let file = match idle.pop() {
+                Some(f) => f,
+                None => break,
+            };
+            let data = read_chunk(&mut local_file, UPLOAD_CHUNK_SIZE)
+                .await
+                .map_err(|e| format!("读取本地文件失败: {}", e))?;
+            if data.is_empty() {
+                eof = true;
+                idle.push(file);
+                break;
+            }
+            let len = data.len() as u64;
+            in_flight.push(write_chunk(file, next_offset, data));
+            pending.insert(next_offset);
+            next_offset += len;
+        }
         
-        // 读取本地文件
-        let data = match tokio::fs::read(&local_path).await {
-            Ok(data) => data,
-            Err(e) => return Err(format!("读取本地文件失败: {}", e)),
+        let (file, chunk_offset, written) = match in_flight.next().await {
+            Some(result) => result?,
+            None => break,
         };
+        idle.push(file);
+        pending.remove(&chunk_offset);
+        uploaded += written as u64;
         
-        // 写入远程文件
-        match session.write(&remote_path, &data).await {
-            Ok(_) => {
-                println!("文件上传成功");
-                Ok(())
-            },
-            Err(e) => Err(format!("写入远程文件失败: {}", e)),
+        // 各块完成顺序不定，记录的进度只算连续写入的部分（用于续传）
+        on_progress(pending.first().copied().unwrap_or(next_offset), total_size);
+        
+        // 只在进度变化时发送更新
+        let progress = if total_size > 0 {
+            ((uploaded as f64 / total_size as f64) * 100.0) as u32
+        } else {
+            100
+        };
+        if progress != last_progress_percent || uploaded == total_size {
+            last_progress_percent = emit_progress(uploaded);
         }
+    }
+    
+    // 关闭所有远程文件句柄
+    for mut file in idle {
+        if let Err(e) = file.shutdown().await {
+            println!("关闭远程文件失败: {}", e);
+        }
+    }
+    if total_size == 0 {
+        emit_progress(0);
+    }
+    if let TransferEnd::Completed = end {
+        println!("文件上传成功: {}", remote_path);
+    }
+    Ok(end)
+}
+
+// 取消进行中的上传
+#[tauri::command]
+pub async fn cancel_upload(app: tauri::AppHandle, upload_id: u32) -> Result<(), String> {
+    println!("取消上传: {}", upload_id);
+    crate::download_manager::cancel_download(app, upload_id).await
 }
 
 // 读取SFTP文件内容
@@ -442,12 +716,26 @@ pub async fn create_sftp_directory(connection_id: String, path: String) -> Resul
         }
 }
 
-// 从SSH会话创建SFTP连接（新实现）
+// 从SSH终端的连接创建SFTP连接（不重新认证）
+// ssh_id 省略时按前端约定从 "sftp-{ssh_id}" 中解析
 #[tauri::command]
-pub fn create_sftp_from_ssh(_connection_id: String) -> Result<(), String> {
-    // TODO: 实现从russh会话创建SFTP连接
-    // 这需要在SSH终端模块中暴露会话，然后在这里复用
-    Err("从SSH会话创建SFTP连接功能正在开发中".to_string())
+pub async fn create_sftp_from_ssh(connection_id: String, ssh_id: Option<String>) -> Result<(), String> {
+    let ssh_id = ssh_id.unwrap_or_else(|| {
+        connection_id.strip_prefix("sftp-").unwrap_or(&connection_id).to_string()
+    });
+    let connection = crate::ssh_terminal_russh::terminal_connection(&ssh_id)
+        .ok_or("SSH终端未找到")?;
+    
+    println!("从SSH终端 {} 创建SFTP通道...", ssh_id);
+    let sftp_session = connection.open_sftp().await?;
+    
+    ssh_session::attach(&ssh_session::sftp_consumer(&connection_id), &connection);
+    SFTP_CONNECTIONS.lock().insert(connection_id, SftpConnection {
+        session: Arc::new(sftp_session),
+        key: connection.key.clone(),
+    });
+    println!("✓ SFTP会话创建成功（复用SSH连接）");
+    Ok(())
 }
 
 // 重命名SFTP文件或目录
@@ -496,7 +784,7 @@ pub async fn delete_sftp_directory(connection_id: String, path: String) -> Resul
 }
 
 // 递归删除目录（包括非空目录）
-fn recursive_delete_dir<'a>(session: &'a SftpSession, path: &'a str) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + 'a>> {
+pub fn recursive_delete_dir<'a>(session: &'a SftpSession, path: &'a str) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + 'a>> {
     Box::pin(async move {
         // 读取目录内容
         let entries = match session.read_dir(path).await {
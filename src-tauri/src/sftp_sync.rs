@@ -0,0 +1,416 @@
+// 目录同步（类似 rsync）：比较本地和远程目录树，生成差异计划（新增、修改、删除），预览后按方向执行
+use russh_sftp::client::SftpSession;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::sftp_dir::{join_relative, DirTransfer, DirTransferOptions, DirTransferReport, EntryKind, FileError, TreeEntry};
+
+// 比较修改时间时允许的误差（秒），部分文件系统只精确到 2 秒
+const MTIME_TOLERANCE: u64 = 2;
+
+// 同步方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncDirection {
+    // 本地 -> 远程
+    #[default]
+    Upload,
+    // 远程 -> 本地
+    Download,
+}
+
+// 文件比较方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareMode {
+    // 大小或修改时间不同即视为已修改
+    #[default]
+    SizeMtime,
+    // 大小相同时比较 SHA-1
+    Checksum,
+}
+
+// 同步选项；过滤、符号链接和权限保留与目录传输相同，修改时间总是保留
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncOptions {
+    pub direction: SyncDirection,
+    pub compare: CompareMode,
+    // 删除目标端多余的文件和目录
+    pub delete: bool,
+    #[serde(flatten)]
+    pub transfer: DirTransferOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncActionKind {
+    New,
+    Changed,
+    Deleted,
+}
+
+// 计划中的一项操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    pub path: String,
+    pub kind: SyncActionKind,
+    pub is_dir: bool,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reason: Option<String>,
+}
+
+// 同步计划（预览结果，也可以原样传给 apply_sftp_sync 执行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    pub direction: SyncDirection,
+    pub local_path: String,
+    pub remote_path: String,
+    pub actions: Vec<SyncAction>,
+    pub new_count: usize,
+    pub changed_count: usize,
+    pub deleted_count: usize,
+    pub unchanged_count: usize,
+    // 需要传输的字节数
+    pub bytes: u64,
+}
+
+// 同步结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    #[serde(flatten)]
+    pub transfer: DirTransferReport,
+    pub deleted: usize,
+}
+
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// 本地文件的 SHA-1
+async fn local_sha1(path: &Path) -> Result<String, String> {
+    use sha1::{Digest, Sha1};
+    use tokio::io::AsyncReadExt;
+    let mut file = tokio::fs::File::open(path).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0u8; 65536];
+    loop {
+        let n = file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex_digest(&hasher.finalize()))
+}
+
+// 远程文件的 SHA-1：优先在服务器上执行 sha1sum，不可用时通过 SFTP 读取后计算
+async fn remote_sha1(connection_id: &str, session: &SftpSession, path: &str) -> Result<String, String> {
+    if let Some(connection) = crate::ssh_session::find_connection(connection_id) {
+        if let Ok(output) = connection.exec(&format!("sha1sum -- {}", shell_quote(path))).await {
+            if let Some(hash) = output.split_whitespace().next().filter(|h| h.len() == 40) {
+                return Ok(hash.to_lowercase());
+            }
+        }
+    }
+    use sha1::{Digest, Sha1};
+    use tokio::io::AsyncReadExt;
+    let mut file = session.open(path).await.map_err(|e| e.to_string())?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0u8; 65536];
+    loop {
+        let n = file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex_digest(&hasher.finalize()))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 读取两端目录树，返回 (源端, 目标端)；目标目录不存在时为空
+async fn load_trees(
+    session: &SftpSession,
+    local_path: &str,
+    remote_path: &str,
+    options: &SyncOptions,
+    cancelled: &AtomicBool,
+) -> Result<(Vec<TreeEntry>, Vec<TreeEntry>), String> {
+    let mut skipped = 0;
+    let local = if Path::new(local_path).is_dir() {
+        crate::sftp_dir::walk_local(Path::new(local_path), &options.transfer, cancelled, &mut skipped)?
+    } else if options.direction == SyncDirection::Upload {
+        return Err(format!("本地目录不存在: {}", local_path));
+    } else {
+        Vec::new()
+    };
+    let remote = if session.try_exists(remote_path).await.unwrap_or(false) {
+        crate::sftp_dir::walk_remote(session, remote_path, &options.transfer, cancelled, &mut skipped).await?
+    } else if options.direction == SyncDirection::Download {
+        return Err(format!("远程目录不存在: {}", remote_path));
+    } else {
+        Vec::new()
+    };
+    Ok(match options.direction {
+        SyncDirection::Upload => (local, remote),
+        SyncDirection::Download => (remote, local),
+    })
+}
+
+// 比较两端目录树，生成同步计划
+async fn build_plan(
+    connection_id: &str,
+    session: &SftpSession,
+    local_path: &str,
+    remote_path: &str,
+    options: &SyncOptions,
+    source: &[TreeEntry],
+    target: &[TreeEntry],
+) -> SyncPlan {
+    let target_map: HashMap<&str, &TreeEntry> = target.iter().map(|e| (e.relative.as_str(), e)).collect();
+    let source_paths: HashSet<&str> = source.iter().map(|e| e.relative.as_str()).collect();
+    let mut plan = SyncPlan {
+        direction: options.direction,
+        local_path: local_path.to_string(),
+        remote_path: remote_path.to_string(),
+        actions: Vec::new(),
+        new_count: 0,
+        changed_count: 0,
+        deleted_count: 0,
+        unchanged_count: 0,
+        bytes: 0,
+    };
+
+    for entry in source {
+        let is_dir = entry.kind == EntryKind::Dir;
+        let reason = match target_map.get(entry.relative.as_str()) {
+            None => {
+                plan.new_count += 1;
+                plan.bytes += entry.size;
+                plan.actions.push(SyncAction { path: entry.relative.clone(), kind: SyncActionKind::New, is_dir, size: entry.size, reason: None });
+                continue;
+            }
+            Some(existing) => match (&entry.kind, &existing.kind) {
+                (EntryKind::Dir, EntryKind::Dir) => None,
+                (EntryKind::Symlink(a), EntryKind::Symlink(b)) => (a != b).then(|| "链接目标不同".to_string()),
+                (EntryKind::File, EntryKind::File) => {
+                    if entry.size != existing.size {
+                        Some("大小不同".to_string())
+                    } else if options.compare == CompareMode::Checksum {
+                        let local_file = Path::new(local_path).join(&entry.relative);
+                        let remote_file = join_relative(remote_path, &entry.relative);
+                        let local_hash = local_sha1(&local_file).await;
+                        let remote_hash = remote_sha1(connection_id, session, &remote_file).await;
+                        match (local_hash, remote_hash) {
+                            (Ok(a), Ok(b)) if a == b => None,
+                            (Ok(_), Ok(_)) => Some("校验和不同".to_string()),
+                            _ => Some("无法计算校验和".to_string()),
+                        }
+                    } else {
+                        match (entry.mtime, existing.mtime) {
+                            (Some(a), Some(b)) if a.abs_diff(b) > MTIME_TOLERANCE => Some("修改时间不同".to_string()),
+                            _ => None,
+                        }
+                    }
+                }
+                _ => Some("类型不同".to_string()),
+            },
+        };
+        match reason {
+            Some(reason) => {
+                plan.changed_count += 1;
+                plan.bytes += entry.size;
+                plan.actions.push(SyncAction { path: entry.relative.clone(), kind: SyncActionKind::Changed, is_dir, size: entry.size, reason: Some(reason) });
+            }
+            None => plan.unchanged_count += 1,
+        }
+    }
+
+    // 目标端多余的条目（只在启用删除时列入计划）
+    if options.delete {
+        for entry in target.iter().filter(|e| !source_paths.contains(e.relative.as_str())) {
+            plan.deleted_count += 1;
+            plan.actions.push(SyncAction {
+                path: entry.relative.clone(),
+                kind: SyncActionKind::Deleted,
+                is_dir: entry.kind == EntryKind::Dir,
+                size: entry.size,
+                reason: None,
+            });
+        }
+    }
+    plan
+}
+
+// 预览同步（dry-run）：只比较，不修改任何文件
+#[tauri::command]
+pub async fn plan_sftp_sync(
+    connection_id: String,
+    local_path: String,
+    remote_path: String,
+    options: Option<SyncOptions>,
+) -> Result<SyncPlan, String> {
+    let session = crate::sftp_russh::sftp_session(&connection_id)?;
+    let options = options.unwrap_or_default();
+    let (source, target) = load_trees(&session, &local_path, &remote_path, &options, &AtomicBool::new(false)).await?;
+    let plan = build_plan(&connection_id, &session, &local_path, &remote_path, &options, &source, &target).await;
+    println!(
+        "同步计划: 新增 {}，修改 {}，删除 {}，未变化 {}",
+        plan.new_count, plan.changed_count, plan.deleted_count, plan.unchanged_count
+    );
+    Ok(plan)
+}
+
+// 执行同步；传入预览得到的计划时按该计划执行，否则重新比较
+#[tauri::command]
+pub async fn apply_sftp_sync(
+    app: tauri::AppHandle,
+    connection_id: String,
+    local_path: String,
+    remote_path: String,
+    transfer_id: u32,
+    options: Option<SyncOptions>,
+    plan: Option<SyncPlan>,
+) -> Result<SyncReport, String> {
+    let session = crate::sftp_russh::sftp_session(&connection_id)?;
+    let mut options = options.unwrap_or_default();
+    if let Some(plan) = &plan {
+        if plan.local_path != local_path || plan.remote_path != remote_path {
+            return Err("同步计划与目录不匹配".to_string());
+        }
+        options.direction = plan.direction;
+    }
+    // 目标端不保留源文件的修改时间时，之后每次按修改时间比较都会认为文件已修改
+    options.transfer.preserve_times = true;
+    // 遍历前登记，遍历大目录时也可以取消
+    let cancelled = crate::sftp_dir::register(transfer_id);
+    let result = match load_trees(&session, &local_path, &remote_path, &options, &cancelled).await {
+        Ok(_) if cancelled.load(Ordering::Relaxed) => {
+            let transfer = DirTransferReport { cancelled: true, ..Default::default() };
+            Ok(SyncReport { transfer, deleted: 0 })
+        }
+        Ok((source, target)) => {
+            let plan = match plan {
+                Some(plan) => plan,
+                None => build_plan(&connection_id, &session, &local_path, &remote_path, &options, &source, &target).await,
+            };
+            println!("执行同步: {} <-> {}（{} 项操作）", local_path, remote_path, plan.actions.len());
+            let context = DirTransfer {
+                app: &app,
+                session: &session,
+                transfer_id,
+                options: &options.transfer,
+                cancelled: &cancelled,
+            };
+            apply_plan(&context, &plan, &source, &target).await
+        }
+        Err(e) => Err(e),
+    };
+    crate::sftp_dir::unregister(transfer_id);
+    result
+}
+
+async fn apply_plan(
+    context: &DirTransfer<'_>,
+    plan: &SyncPlan,
+    source: &[TreeEntry],
+    target: &[TreeEntry],
+) -> Result<SyncReport, String> {
+    let session = context.session;
+    let mut report = SyncReport::default();
+    let local_root = plan.local_path.as_str();
+    let remote_root = plan.remote_path.as_str();
+    let target_map: HashMap<&str, &TreeEntry> = target.iter().map(|e| (e.relative.as_str(), e)).collect();
+
+    // 类型改变的条目先删除目标端的旧条目
+    let mut wanted = HashSet::new();
+    for action in &plan.actions {
+        if action.kind == SyncActionKind::Deleted {
+            continue;
+        }
+        wanted.insert(action.path.as_str());
+        let old = match target_map.get(action.path.as_str()) {
+            Some(old) => old,
+            None => continue,
+        };
+        let source_kind = source.iter().find(|e| e.relative == action.path).map(|e| &e.kind);
+        let same_type = matches!(
+            (source_kind, &old.kind),
+            (Some(EntryKind::Dir), EntryKind::Dir) | (Some(EntryKind::File), EntryKind::File)
+        );
+        if !same_type {
+            if let Err(error) = remove_entry(session, plan.direction, local_root, remote_root, old).await {
+                report.transfer.errors.push(FileError { path: action.path.clone(), error });
+            }
+        }
+    }
+
+    // 传输新增和修改的条目（使用当前的源端属性）
+    let entries: Vec<TreeEntry> = source.iter().filter(|e| wanted.contains(e.relative.as_str())).cloned().collect();
+    match plan.direction {
+        SyncDirection::Upload => {
+            crate::sftp_dir::upload_entries(context, local_root, remote_root, &entries, &mut report.transfer).await?
+        }
+        SyncDirection::Download => {
+            crate::sftp_dir::download_entries(context, remote_root, local_root, &entries, &mut report.transfer).await?
+        }
+    }
+    if report.transfer.cancelled {
+        return Ok(report);
+    }
+
+    // 删除目标端多余的条目（由深到浅，目录在其内容之后删除）
+    for action in plan.actions.iter().rev().filter(|a| a.kind == SyncActionKind::Deleted) {
+        let entry = match target_map.get(action.path.as_str()) {
+            Some(entry) => *entry,
+            // 预览之后已被删除
+            None => continue,
+        };
+        match remove_entry(session, plan.direction, local_root, remote_root, entry).await {
+            Ok(_) => report.deleted += 1,
+            Err(error) => report.transfer.errors.push(FileError { path: action.path.clone(), error }),
+        }
+    }
+    println!("同步完成: 传输 {} 个文件，删除 {} 项，{} 个错误", report.transfer.files, report.deleted, report.transfer.errors.len());
+    Ok(report)
+}
+
+// 删除目标端的条目（上传时删除远程，下载时删除本地）
+async fn remove_entry(
+    session: &SftpSession,
+    direction: SyncDirection,
+    local_root: &str,
+    remote_root: &str,
+    entry: &TreeEntry,
+) -> Result<(), String> {
+    match direction {
+        SyncDirection::Upload => {
+            let path = join_relative(remote_root, &entry.relative);
+            if entry.kind == EntryKind::Dir {
+                crate::sftp_russh::recursive_delete_dir(session, &path).await
+            } else {
+                session.remove_file(&path).await.map_err(|e| format!("删除远程文件失败: {}", e))
+            }
+        }
+        SyncDirection::Download => {
+            let path = crate::sftp_dir::local_target(Path::new(local_root), &entry.relative)?;
+            if entry.kind == EntryKind::Dir {
+                match std::fs::remove_dir_all(&path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("删除本地目录失败: {}", e)),
+                    _ => Ok(()),
+                }
+            } else {
+                std::fs::remove_file(&path).map_err(|e| format!("删除本地文件失败: {}", e))
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+// 本地终端的 Shell 配置：检测已安装的 Shell，并保存用户自定义的 Shell 配置
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+// 检测到的 Shell 使用的ID前缀，这类配置不写入磁盘
+const DETECTED_PREFIX: &str = "detected-";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShellProfile {
+    pub id: String,
+    pub name: String,
+    pub program: String,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub args: Vec<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub login: bool, // 以登录 Shell 方式启动
+    #[serde(default)]
+    pub detected: bool, // 自动检测到的 Shell
+}
+
+impl ShellProfile {
+    fn detected(key: &str, name: &str, program: String, args: Vec<String>) -> Self {
+        ShellProfile {
+            id: format!("{}{}", DETECTED_PREFIX, key),
+            name: name.to_string(),
+            program,
+            args,
+            detected: true,
+            ..Default::default()
+        }
+    }
+
+    // 最终的启动参数：登录 Shell 时按 Shell 类型补充登录参数
+    pub fn launch_args(&self) -> Vec<String> {
+        let mut args = self.args.clone();
+        if self.login {
+            let program = Path::new(&self.program)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+            let flag = match program.as_str() {
+                "bash" | "zsh" | "fish" | "sh" | "dash" | "ksh" => Some("-l"),
+                "pwsh" if !cfg!(windows) => Some("-Login"),
+                _ => None,
+            };
+            if let Some(flag) = flag {
+                if !args.iter().any(|a| a == flag || a == "--login") {
+                    args.insert(0, flag.to_string());
+                }
+            }
+        }
+        args
+    }
+
+    // 工作目录（展开 ~）
+    pub fn working_dir(&self) -> Option<String> {
+        let cwd = self.cwd.as_ref()?;
+        match cwd.strip_prefix('~') {
+            Some(rest) => dirs::home_dir().map(|home| {
+                home.join(rest.trim_start_matches(['/', '\\'])).to_string_lossy().to_string()
+            }),
+            None => Some(cwd.clone()),
+        }
+    }
+}
+
+fn shell_profiles_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let dir = proj.config_dir().join("shell_profiles");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+// 在 PATH 中查找可执行文件
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let candidates: Vec<String> = if cfg!(windows) {
+        vec![format!("{}.exe", name), format!("{}.cmd", name)]
+    } else {
+        vec![name.to_string()]
+    };
+    std::env::split_paths(&path)
+        .flat_map(|dir| candidates.iter().map(move |c| dir.join(c)))
+        .find(|p| p.is_file())
+}
+
+// 列出已安装的 WSL 发行版（wsl.exe 输出为 UTF-16LE）
+#[cfg(windows)]
+fn wsl_distros() -> Vec<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = match std::process::Command::new("wsl.exe")
+        .args(["-l", "-q"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        Ok(o) if o.status.success() => o.stdout,
+        _ => return Vec::new(),
+    };
+    let units: Vec<u16> = output.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+        .lines()
+        .map(|l| l.trim().trim_matches('\0').to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+// 检测已安装的 Shell
+#[tauri::command]
+pub fn detect_shells() -> Vec<ShellProfile> {
+    let mut shells = Vec::new();
+
+    #[cfg(windows)]
+    {
+        shells.push(ShellProfile::detected("cmd", "命令提示符", "cmd.exe".to_string(), Vec::new()));
+        if let Some(path) = find_in_path("powershell") {
+            shells.push(ShellProfile::detected(
+                "powershell",
+                "Windows PowerShell",
+                path.to_string_lossy().to_string(),
+                vec!["-NoLogo".to_string()],
+            ));
+        }
+        for (name, label) in [("pwsh", "PowerShell"), ("nu", "Nushell")] {
+            if let Some(path) = find_in_path(name) {
+                shells.push(ShellProfile::detected(name, label, path.to_string_lossy().to_string(), Vec::new()));
+            }
+        }
+        let git_bash = PathBuf::from(r"C:\Program Files\Git\bin\bash.exe");
+        if git_bash.is_file() {
+            shells.push(ShellProfile::detected(
+                "git-bash",
+                "Git Bash",
+                git_bash.to_string_lossy().to_string(),
+                vec!["--login".to_string(), "-i".to_string()],
+            ));
+        }
+        for distro in wsl_distros() {
+            let key: String = distro
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+                .collect();
+            shells.push(ShellProfile::detected(
+                &format!("wsl-{}", key),
+                &format!("WSL: {}", distro),
+                "wsl.exe".to_string(),
+                vec!["-d".to_string(), distro.clone()],
+            ));
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        // /etc/shells 中登记的 Shell 优先，其余在 PATH 中查找
+        let registered: Vec<PathBuf> = fs::read_to_string("/etc/shells")
+            .unwrap_or_default()
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(PathBuf::from)
+            .filter(|p| p.is_file())
+            .collect();
+        for name in ["bash", "zsh", "fish", "nu", "pwsh"] {
+            let path = registered
+                .iter()
+                .find(|p| p.file_name().and_then(|n| n.to_str()) == Some(name))
+                .cloned()
+                .or_else(|| find_in_path(name));
+            if let Some(path) = path {
+                shells.push(ShellProfile::detected(name, name, path.to_string_lossy().to_string(), Vec::new()));
+            }
+        }
+    }
+
+    shells
+}
+
+// 按ID查找 Shell 配置（用户保存的配置或检测到的 Shell）
+pub fn find_shell_profile(app: &AppHandle, id: &str) -> Result<ShellProfile, String> {
+    if id.starts_with(DETECTED_PREFIX) {
+        return detect_shells()
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("未找到Shell: {}", id));
+    }
+    let path = shell_profiles_dir(app)?.join(format!("{}.json", id));
+    let txt = fs::read_to_string(path).map_err(|_| format!("Shell配置不存在: {}", id))?;
+    serde_json::from_str(&txt).map_err(|e| e.to_string())
+}
+
+// 列出所有 Shell 配置：检测到的 Shell 在前，用户配置在后
+#[tauri::command]
+pub fn list_shell_profiles(app: AppHandle) -> Result<Vec<ShellProfile>, String> {
+    let mut profiles = detect_shells();
+    let dir = shell_profiles_dir(&app)?;
+    let mut saved = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            if let Ok(txt) = fs::read_to_string(&path) {
+                if let Ok(profile) = serde_json::from_str::<ShellProfile>(&txt) {
+                    saved.push(profile);
+                }
+            }
+        }
+    }
+    saved.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles.extend(saved);
+    Ok(profiles)
+}
+
+#[tauri::command]
+pub fn save_shell_profile(app: AppHandle, mut profile: ShellProfile) -> Result<(), String> {
+    if profile.id.is_empty() || profile.id.starts_with(DETECTED_PREFIX) {
+        return Err("无效的Shell配置ID".into());
+    }
+    if profile.program.trim().is_empty() {
+        return Err("Shell程序不能为空".into());
+    }
+    profile.detected = false;
+    let path = shell_profiles_dir(&app)?.join(format!("{}.json", profile.id));
+    let data = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_shell_profile(app: AppHandle, id: String) -> Result<(), String> {
+    let path = shell_profiles_dir(&app)?.join(format!("{}.json", id));
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -1,10 +1,11 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SshProfileMeta {
   pub id: String,
   pub host: String,
@@ -19,6 +20,166 @@ pub struct SshProfileMeta {
   pub group: Option<String>, // 分组名称
   #[serde(skip_serializing_if = "Vec::is_empty", default)]
   pub tags: Vec<String>, // 标签列表
+  #[serde(default)]
+  pub use_agent: bool, // 是否使用 ssh-agent 认证
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub jump_hosts: Vec<String>, // 跳板机配置ID列表，按连接顺序排列
+  #[serde(skip_serializing_if = "Vec::is_empty", default)]
+  pub forwards: Vec<crate::port_forward::PortForwardSpec>, // 连接后自动启动的端口转发
+  #[serde(default)]
+  pub reconnect: ReconnectPolicy, // 断线自动重连策略
+  #[serde(default)]
+  pub timeouts: ConnectionTimeouts, // 保活与超时设置
+  #[serde(default)]
+  pub always_record: bool, // 连接时自动录制终端会话
+  #[serde(default)]
+  pub terminal: TerminalOptions, // 终端环境变量、TERM、终端模式和启动命令
+  #[serde(default)]
+  pub transport: Transport, // 终端传输方式：SSH 或 Mosh
+  #[serde(default)]
+  pub mosh: MoshOptions, // Mosh 传输设置
+}
+
+// 终端传输方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+  #[default]
+  Ssh,
+  // 通过SSH启动 mosh-server，再运行本机安装的 mosh-client 经UDP连接；不支持跳板机
+  Mosh,
+}
+
+// Mosh 传输设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoshOptions {
+  // 远程 mosh-server 路径，默认在 PATH 中查找
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub server_path: Option<String>,
+  // 本地 mosh-client 路径，默认在 PATH 中查找；找不到时连接失败
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub client_path: Option<String>,
+  // 服务端UDP端口或端口范围，如 60001 或 60001:60010
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub port_range: Option<String>,
+  // 本地回显预测：adaptive（默认）/ always / never
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub prediction: Option<String>,
+}
+
+// SSH终端选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalOptions {
+  // TERM 值，默认 xterm-256color
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub term: Option<String>,
+  // 区域设置，同时作为 LANG 和 LC_ALL 发送（env 中已指定的优先）
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub locale: Option<String>,
+  // 通过 SSH env 请求发送的环境变量（服务器需在 AcceptEnv 中允许）
+  #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+  pub env: BTreeMap<String, String>,
+  // 终端模式，如 VERASE=127、IUTF8=1
+  #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+  pub modes: BTreeMap<String, u32>,
+  // shell 启动后执行的命令（可多行）
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub startup_command: Option<String>,
+}
+
+impl TerminalOptions {
+  pub fn term(&self) -> &str {
+    self.term.as_deref().filter(|t| !t.is_empty()).unwrap_or("xterm-256color")
+  }
+
+  // 需要发送的全部环境变量
+  pub fn environment(&self) -> BTreeMap<String, String> {
+    let mut env = self.env.clone();
+    if let Some(locale) = self.locale.as_ref().filter(|l| !l.is_empty()) {
+      for key in ["LANG", "LC_ALL"] {
+        env.entry(key.to_string()).or_insert_with(|| locale.clone());
+      }
+    }
+    env
+  }
+}
+
+// 连接保活与超时设置（单位：秒，0 表示不启用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionTimeouts {
+  pub keepalive_interval: u64, // 保活包发送间隔
+  pub keepalive_max: usize,    // 连续多少次保活无响应后断开
+  pub connect_timeout: u64,    // TCP连接和握手超时（每一跳单独计时）
+  pub inactivity_timeout: u64, // 无任何数据往来时断开
+}
+
+impl Default for ConnectionTimeouts {
+  fn default() -> Self {
+    ConnectionTimeouts {
+      keepalive_interval: 30,
+      keepalive_max: 3,
+      connect_timeout: 15,
+      inactivity_timeout: 300,
+    }
+  }
+}
+
+impl ConnectionTimeouts {
+  fn secs(value: u64) -> Option<std::time::Duration> {
+    (value > 0).then(|| std::time::Duration::from_secs(value))
+  }
+
+  pub fn keepalive_interval(&self) -> Option<std::time::Duration> {
+    Self::secs(self.keepalive_interval)
+  }
+
+  pub fn connect_timeout(&self) -> Option<std::time::Duration> {
+    Self::secs(self.connect_timeout)
+  }
+
+  pub fn inactivity_timeout(&self) -> Option<std::time::Duration> {
+    Self::secs(self.inactivity_timeout)
+  }
+}
+
+// 断线重连策略（指数退避）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectPolicy {
+  pub enabled: bool,
+  pub max_attempts: u32,
+  pub initial_delay_ms: u64,
+  pub max_delay_ms: u64,
+  // 重连后重新附加的远程会话：tmux / screen
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub reattach: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub session_name: Option<String>,
+}
+
+impl Default for ReconnectPolicy {
+  fn default() -> Self {
+    ReconnectPolicy {
+      enabled: true,
+      max_attempts: 5,
+      initial_delay_ms: 1000,
+      max_delay_ms: 30000,
+      reattach: None,
+      session_name: None,
+    }
+  }
+}
+
+impl ReconnectPolicy {
+  // 第 attempt 次重连前的等待时间
+  pub fn delay_for(&self, attempt: u32) -> std::time::Duration {
+    let factor = 1u64.checked_shl(attempt.saturating_sub(1).min(16)).unwrap_or(u64::MAX);
+    let delay = self.initial_delay_ms.saturating_mul(factor).min(self.max_delay_ms);
+    std::time::Duration::from_millis(delay)
+  }
 }
 
 pub fn profiles_dir(_app: &AppHandle) -> Result<PathBuf, String> {
@@ -29,17 +190,40 @@ pub fn profiles_dir(_app: &AppHandle) -> Result<PathBuf, String> {
   Ok(dir)
 }
 
-#[tauri::command]
-pub fn save_ssh_profile(app: AppHandle, profile: SshProfileMeta, password: Option<String>) -> Result<(), String> {
-  let dir = profiles_dir(&app)?;
+// 私钥口令在 keyring 中的条目名
+fn passphrase_entry(id: &str) -> Result<keyring::Entry, String> {
+  keyring::Entry::new("Termlink", &format!("{}:passphrase", id)).map_err(|e| e.to_string())
+}
+
+pub fn load_ssh_profile(app: &AppHandle, id: &str) -> Result<SshProfileMeta, String> {
+  let path = profiles_dir(app)?.join(format!("{}.json", id));
+  let txt = fs::read_to_string(path).map_err(|e| format!("读取SSH配置失败: {}", e))?;
+  serde_json::from_str(&txt).map_err(|e| e.to_string())
+}
+
+pub fn store_ssh_profile(app: &AppHandle, profile: &SshProfileMeta) -> Result<(), String> {
+  let dir = profiles_dir(app)?;
   let path = dir.join(format!("{}.json", profile.id));
-  let data = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
-  fs::write(path, data).map_err(|e| e.to_string())?;
+  let data = serde_json::to_string_pretty(profile).map_err(|e| e.to_string())?;
+  fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_ssh_profile(
+  app: AppHandle,
+  profile: SshProfileMeta,
+  password: Option<String>,
+  passphrase: Option<String>,
+) -> Result<(), String> {
+  store_ssh_profile(&app, &profile)?;
   if profile.save_password {
     if let Some(pw) = password {
       let entry = keyring::Entry::new("Termlink", &profile.id).map_err(|e| e.to_string())?;
       entry.set_password(&pw).map_err(|e| e.to_string())?;
     }
+    if let Some(phrase) = passphrase {
+      store_ssh_key_passphrase(&profile.id, &phrase)?;
+    }
   }
   Ok(())
 }
@@ -70,14 +254,24 @@ pub fn get_ssh_password(id: String) -> Result<Option<String>, String> {
   }
 }
 
+#[tauri::command]
+pub fn get_ssh_key_passphrase(id: String) -> Result<Option<String>, String> {
+  match passphrase_entry(&id)?.get_password() {
+    Ok(phrase) => Ok(Some(phrase)),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(e) => Err(e.to_string()),
+  }
+}
+
+// 保存私钥口令（连接时用户在口令提示中选择了记住口令）
+pub fn store_ssh_key_passphrase(id: &str, passphrase: &str) -> Result<(), String> {
+  passphrase_entry(id)?.set_password(passphrase).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn restart_ssh_connection(_app: AppHandle, id: String, _profile_id: String) -> Result<(), String> {
-  // 先关闭当前连接
-  crate::terminal::close_pty(id)?;
-  
-  // TODO: 重新启动SSH连接的逻辑可以在前端实现
-  // 这里只是清理资源
-  Ok(())
+  // 由后端在同一终端ID下重新建立连接
+  crate::ssh_terminal_russh::request_reconnect(&id)
 }
 
 #[tauri::command]
@@ -93,6 +287,9 @@ pub fn delete_ssh_profile(app: AppHandle, profile_id: String) -> Result<(), Stri
   // 删除保存的密码
   let entry = keyring::Entry::new("Termlink", &profile_id).map_err(|e| e.to_string())?;
   let _ = entry.delete_password(); // 忽略删除密码的错误，因为可能没有保存密码
+  if let Ok(entry) = passphrase_entry(&profile_id) {
+    let _ = entry.delete_password();
+  }
   
   Ok(())
 }
@@ -0,0 +1,459 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use russh::client;
+use russh::client::KeyboardInteractiveAuthResponse;
+use russh_keys::key;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+// 私钥已加密但未提供口令时返回的错误，前端据此弹出口令输入框
+pub const PASSPHRASE_REQUIRED_ERROR: &str = "私钥已加密，需要输入口令";
+
+// keyboard-interactive 最多进行的交互轮数
+const MAX_INTERACTIVE_ROUNDS: usize = 10;
+
+// 私钥口令最多询问的次数
+const MAX_PASSPHRASE_ATTEMPTS: usize = 3;
+
+// 等待用户回答提示的超时时间
+const PROMPT_TIMEOUT_SECS: u64 = 300;
+
+// 发送给前端的单个提示
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthPromptItem {
+    pub prompt: String,
+    pub echo: bool,
+}
+
+// 提示的来源
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthPromptKind {
+    // 服务器的 keyboard-interactive 提示
+    KeyboardInteractive,
+    // 加密私钥的口令
+    Passphrase,
+}
+
+// ssh_auth_prompt://{id}（终端）或 ssh_auth_prompt（SFTP、监控等没有终端的连接）事件的内容
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthPromptRequest {
+    // 回答时传给 respond_ssh_auth_prompt 的ID
+    pub id: String,
+    pub kind: AuthPromptKind,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<AuthPromptItem>,
+    // 是否可以让用户选择记住输入（仅已保存配置的私钥口令）
+    pub remember: bool,
+}
+
+// 前端对认证提示的回答
+struct AuthPromptAnswer {
+    responses: Vec<String>,
+    remember: bool,
+}
+
+// 等待前端回答的认证提示（回答为 None 表示用户取消）
+struct PendingPrompt {
+    request: AuthPromptRequest,
+    shared: bool,
+    sender: oneshot::Sender<Option<AuthPromptAnswer>>,
+}
+
+static PENDING_PROMPTS: Lazy<Mutex<HashMap<String, PendingPrompt>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 把服务器提示转发给前端的通道
+#[derive(Clone)]
+pub struct AuthPrompter {
+    app: AppHandle,
+    id: String,
+    // 为 true 时发出所有窗口都监听的 ssh_auth_prompt 事件，由全局对话框回答
+    shared: bool,
+}
+
+impl AuthPrompter {
+    // 发出提示事件并等待 respond_ssh_auth_prompt 的回答
+    async fn ask(&self, mut request: AuthPromptRequest) -> Result<AuthPromptAnswer, String> {
+        request.id = self.id.clone();
+        let (tx, rx) = oneshot::channel();
+        PENDING_PROMPTS.lock().insert(
+            self.id.clone(),
+            PendingPrompt { request: request.clone(), shared: self.shared, sender: tx },
+        );
+        let event = if self.shared {
+            "ssh_auth_prompt".to_string()
+        } else {
+            format!("ssh_auth_prompt://{}", self.id)
+        };
+        let _ = self.app.emit(&event, request);
+
+        let answer = tokio::time::timeout(std::time::Duration::from_secs(PROMPT_TIMEOUT_SECS), rx).await;
+        PENDING_PROMPTS.lock().remove(&self.id);
+        if self.shared {
+            // 通知其他窗口关闭同一提示的对话框
+            let _ = self.app.emit("ssh_auth_prompt_closed", &self.id);
+        }
+        match answer {
+            Ok(Ok(Some(answer))) => Ok(answer),
+            Ok(Ok(None)) => Err("用户取消了认证".to_string()),
+            Ok(Err(_)) => Err("认证提示已失效".to_string()),
+            Err(_) => Err("等待认证输入超时".to_string()),
+        }
+    }
+}
+
+// SSH认证参数
+#[derive(Clone, Default)]
+pub struct AuthOptions {
+    pub username: String,
+    pub password: Option<String>,
+    pub private_key: Option<String>,
+    pub passphrase: Option<String>,
+    pub use_agent: bool,
+    pub prompter: Option<AuthPrompter>,
+    // 已保存的配置ID，用户选择记住口令时写入 keyring
+    pub profile_id: Option<String>,
+}
+
+impl AuthOptions {
+    // 组合前端传入的参数和已保存的配置（私钥路径、keyring中的口令）
+    pub fn resolve(
+        app: &AppHandle,
+        username: &str,
+        password: Option<String>,
+        private_key: Option<String>,
+        passphrase: Option<String>,
+        profile_id: Option<&str>,
+    ) -> Self {
+        let profile = profile_id.and_then(|id| crate::ssh::load_ssh_profile(app, id).ok());
+        let private_key = private_key
+            .filter(|k| !k.trim().is_empty())
+            .or_else(|| profile.as_ref().and_then(|p| p.private_key.clone()))
+            .filter(|k| !k.trim().is_empty());
+        let passphrase = passphrase.or_else(|| {
+            if private_key.is_some() {
+                profile_id.and_then(|id| crate::ssh::get_ssh_key_passphrase(id.to_string()).ok().flatten())
+            } else {
+                None
+            }
+        });
+        AuthOptions {
+            username: username.to_string(),
+            password: password.filter(|p| !p.is_empty()),
+            private_key,
+            passphrase,
+            use_agent: profile.map(|p| p.use_agent).unwrap_or(false),
+            prompter: None,
+            profile_id: profile_id.map(|id| id.to_string()),
+        }
+    }
+
+    // 允许通过 ssh_auth_prompt://{id} 事件向终端询问 keyboard-interactive 提示和私钥口令
+    pub fn with_prompts(mut self, app: &AppHandle, id: &str) -> Self {
+        self.prompter = Some(AuthPrompter {
+            app: app.clone(),
+            id: id.to_string(),
+            shared: false,
+        });
+        self
+    }
+
+    // 没有终端的使用者（SFTP、监控）：通过所有窗口都监听的 ssh_auth_prompt 事件询问
+    // id 使用使用者ID，避免与同ID终端的提示互相覆盖
+    pub fn with_shared_prompts(mut self, app: &AppHandle, consumer: &str) -> Self {
+        self.prompter = Some(AuthPrompter {
+            app: app.clone(),
+            id: consumer.to_string(),
+            shared: true,
+        });
+        self
+    }
+
+    // 描述将要尝试的认证方式（用于日志）
+    pub fn describe(&self) -> String {
+        let mut methods = Vec::new();
+        if self.private_key.is_some() {
+            methods.push("私钥认证");
+        }
+        if self.use_agent || (self.private_key.is_none() && self.password.is_none()) {
+            methods.push("ssh-agent认证");
+        }
+        if self.password.is_some() {
+            methods.push("密码认证");
+        }
+        if self.prompter.is_some() || self.password.is_some() {
+            methods.push("keyboard-interactive认证");
+        }
+        methods.join(" / ")
+    }
+}
+
+// 展开 ~ 开头的路径
+fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+// 加载私钥：既支持文件路径，也支持直接粘贴的私钥内容（OpenSSH / PEM）
+pub fn load_private_key(private_key: &str, passphrase: Option<&str>) -> Result<key::KeyPair, String> {
+    let result = if private_key.trim_start().starts_with("-----BEGIN") {
+        russh_keys::decode_secret_key(private_key.trim(), passphrase)
+    } else {
+        russh_keys::load_secret_key(expand_home(private_key.trim()), passphrase)
+    };
+    result.map_err(|e| match e {
+        russh_keys::Error::KeyIsEncrypted => PASSPHRASE_REQUIRED_ERROR.to_string(),
+        _ if passphrase.is_some() => format!("加载私钥失败（口令可能错误）: {}", e),
+        _ => format!("加载私钥失败: {}", e),
+    })
+}
+
+// 加载认证用的私钥；私钥已加密且没有保存口令时向用户询问口令，用户选择记住时保存到 keyring
+async fn load_auth_key(private_key: &str, options: &AuthOptions) -> Result<key::KeyPair, String> {
+    let mut result = load_private_key(private_key, options.passphrase.as_deref());
+    let prompter = match &options.prompter {
+        Some(prompter) if options.passphrase.is_none() => prompter,
+        _ => return result,
+    };
+    if !matches!(&result, Err(e) if e == PASSPHRASE_REQUIRED_ERROR) {
+        return result;
+    }
+    // 粘贴的私钥内容不显示在提示中
+    let label = if private_key.trim_start().starts_with("-----BEGIN") { "私钥" } else { private_key.trim() };
+    for attempt in 0..MAX_PASSPHRASE_ATTEMPTS {
+        let request = AuthPromptRequest {
+            id: String::new(),
+            kind: AuthPromptKind::Passphrase,
+            name: "私钥口令".to_string(),
+            instructions: if attempt == 0 {
+                format!("{} 已加密", label)
+            } else {
+                "口令错误，请重试".to_string()
+            },
+            prompts: vec![AuthPromptItem { prompt: "口令: ".to_string(), echo: false }],
+            remember: options.profile_id.is_some(),
+        };
+        println!("等待用户输入私钥口令...");
+        let answer = prompter.ask(request).await?;
+        let passphrase = answer.responses.into_iter().next().unwrap_or_default();
+        result = load_private_key(private_key, Some(&passphrase));
+        if result.is_ok() {
+            if let (true, Some(id)) = (answer.remember, &options.profile_id) {
+                match crate::ssh::store_ssh_key_passphrase(id, &passphrase) {
+                    Ok(()) => println!("已保存私钥口令: {}", id),
+                    Err(e) => println!("保存私钥口令失败: {}", e),
+                }
+            }
+            break;
+        }
+    }
+    result
+}
+
+// 通过 SSH_AUTH_SOCK 指向的 ssh-agent 认证
+#[cfg(unix)]
+async fn authenticate_agent<H: client::Handler>(
+    session: &mut client::Handle<H>,
+    username: &str,
+) -> Result<bool, String> {
+    if std::env::var_os("SSH_AUTH_SOCK").is_none() {
+        return Ok(false);
+    }
+    let mut agent = russh_keys::agent::client::AgentClient::connect_env()
+        .await
+        .map_err(|e| format!("连接ssh-agent失败: {}", e))?;
+    let identities = agent
+        .request_identities()
+        .await
+        .map_err(|e| format!("读取ssh-agent密钥失败: {}", e))?;
+    println!("ssh-agent 提供 {} 个密钥", identities.len());
+    for public_key in identities {
+        let fingerprint = public_key.fingerprint();
+        let (returned, result) = session.authenticate_future(username, public_key, agent).await;
+        agent = returned;
+        match result {
+            Ok(true) => {
+                println!("✓ ssh-agent认证成功: {}", fingerprint);
+                return Ok(true);
+            }
+            Ok(false) => {}
+            Err(e) => println!("ssh-agent签名失败: {:?}", e),
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(not(unix))]
+async fn authenticate_agent<H: client::Handler>(
+    _session: &mut client::Handle<H>,
+    _username: &str,
+) -> Result<bool, String> {
+    Ok(false)
+}
+
+// 判断提示是否在询问登录密码
+fn is_password_prompt(prompt: &AuthPromptItem) -> bool {
+    !prompt.echo && prompt.prompt.to_lowercase().contains("password")
+}
+
+// keyboard-interactive 认证：服务器的每一轮提示都转发给前端（如 TOTP 验证码）
+// 单独的密码提示会先用已知密码自动回答一次
+async fn authenticate_interactive<H: client::Handler>(
+    session: &mut client::Handle<H>,
+    options: &AuthOptions,
+) -> Result<bool, String> {
+    let mut response = session
+        .authenticate_keyboard_interactive_start(options.username.as_str(), None::<String>)
+        .await
+        .map_err(|e| format!("keyboard-interactive认证过程失败: {}", e))?;
+    let mut password_used = false;
+
+    for _ in 0..MAX_INTERACTIVE_ROUNDS {
+        let (name, instructions, prompts) = match response {
+            KeyboardInteractiveAuthResponse::Success => {
+                println!("✓ keyboard-interactive认证成功");
+                return Ok(true);
+            }
+            KeyboardInteractiveAuthResponse::Failure => return Ok(false),
+            KeyboardInteractiveAuthResponse::InfoRequest { name, instructions, prompts } => {
+                (name, instructions, prompts)
+            }
+        };
+        let request = AuthPromptRequest {
+            id: String::new(),
+            kind: AuthPromptKind::KeyboardInteractive,
+            name,
+            instructions,
+            prompts: prompts
+                .into_iter()
+                .map(|p| AuthPromptItem { prompt: p.prompt, echo: p.echo })
+                .collect(),
+            remember: false,
+        };
+
+        let answers = if request.prompts.is_empty() {
+            // 部分服务器会发送没有提示的轮次，直接回复空列表
+            Vec::new()
+        } else if !password_used
+            && request.prompts.len() == 1
+            && is_password_prompt(&request.prompts[0])
+            && options.password.is_some()
+        {
+            password_used = true;
+            vec![options.password.clone().unwrap_or_default()]
+        } else if let Some(prompter) = &options.prompter {
+            println!("等待用户回答 {} 个认证提示...", request.prompts.len());
+            prompter.ask(request).await?.responses
+        } else {
+            return Ok(false);
+        };
+
+        response = session
+            .authenticate_keyboard_interactive_respond(answers)
+            .await
+            .map_err(|e| format!("keyboard-interactive认证过程失败: {}", e))?;
+    }
+    Err("keyboard-interactive认证轮数过多".to_string())
+}
+
+// 依次尝试 私钥 -> ssh-agent -> 密码 -> keyboard-interactive 认证
+pub async fn authenticate<H: client::Handler>(
+    session: &mut client::Handle<H>,
+    options: &AuthOptions,
+) -> Result<(), String> {
+    let username = options.username.as_str();
+    let mut errors = Vec::new();
+
+    if let Some(private_key) = &options.private_key {
+        println!("开始私钥认证...");
+        // 私钥无法加载时记录错误，继续尝试其他认证方式
+        match load_auth_key(private_key, options).await {
+            Ok(key_pair) => match session.authenticate_publickey(username, Arc::new(key_pair)).await {
+                Ok(true) => {
+                    println!("✓ 私钥认证成功");
+                    return Ok(());
+                }
+                Ok(false) => errors.push("私钥认证失败：服务器拒绝了该密钥".to_string()),
+                Err(e) => errors.push(format!("私钥认证过程失败: {}", e)),
+            },
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if options.use_agent || (options.private_key.is_none() && options.password.is_none()) {
+        println!("开始ssh-agent认证...");
+        match authenticate_agent(session, username).await {
+            Ok(true) => return Ok(()),
+            Ok(false) => errors.push("ssh-agent认证失败：没有可用的密钥".to_string()),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if let Some(pwd) = &options.password {
+        println!("开始密码认证...");
+        match session.authenticate_password(username, pwd).await {
+            Ok(true) => {
+                println!("✓ 密码认证成功");
+                return Ok(());
+            }
+            Ok(false) => errors.push("密码认证失败：用户名或密码错误".to_string()),
+            Err(e) => errors.push(format!("密码认证过程失败: {}", e)),
+        }
+    }
+
+    if options.prompter.is_some() || options.password.is_some() {
+        println!("开始keyboard-interactive认证...");
+        match authenticate_interactive(session, options).await {
+            Ok(true) => return Ok(()),
+            Ok(false) => errors.push("keyboard-interactive认证失败".to_string()),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Err("没有可用的认证方式".to_string())
+    } else {
+        Err(errors.join("；"))
+    }
+}
+
+// 前端回答认证提示；responses 为 None 表示取消，remember 表示用户选择记住输入的口令
+#[tauri::command]
+pub fn respond_ssh_auth_prompt(
+    id: String,
+    responses: Option<Vec<String>>,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    let answer = responses.map(|responses| AuthPromptAnswer { responses, remember: remember.unwrap_or(false) });
+    match PENDING_PROMPTS.lock().remove(&id) {
+        Some(pending) => pending.sender.send(answer).map_err(|_| "认证提示已失效".to_string()),
+        None => Err("没有等待回答的认证提示".to_string()),
+    }
+}
+
+// 获取正在等待回答的认证提示（前端开始监听之前发出的事件可能已错过）
+#[tauri::command]
+pub fn pending_ssh_auth_prompt(id: String) -> Option<AuthPromptRequest> {
+    PENDING_PROMPTS.lock().get(&id).map(|pending| pending.request.clone())
+}
+
+// 获取所有正在等待回答的全局认证提示（新打开的窗口使用）
+#[tauri::command]
+pub fn pending_shared_ssh_auth_prompts() -> Vec<AuthPromptRequest> {
+    PENDING_PROMPTS
+        .lock()
+        .values()
+        .filter(|pending| pending.shared)
+        .map(|pending| pending.request.clone())
+        .collect()
+}
@@ -0,0 +1,743 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use crate::known_hosts::match_wildcard;
+use crate::ssh::{ConnectionTimeouts, SshProfileMeta};
+
+// 导入的配置ID前缀，用于同步时识别
+const IMPORTED_PREFIX: &str = "sshcfg-";
+
+// 导入配置所在分组
+const IMPORTED_GROUP: &str = "SSH Config";
+
+// Include 的最大嵌套深度
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+// 导入计划中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct SshConfigImportItem {
+    pub action: String, // create / update / unchanged / remove
+    pub alias: String,
+    pub profile: SshProfileMeta,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+// 一个 Host 块（Include 的内容会按位置展开）
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, Vec<String>)>,
+}
+
+// ProxyJump 中的一跳：[user@]host[:port]
+struct JumpSpec {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+}
+
+fn home_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"))
+}
+
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    if path == "~" {
+        return home_dir();
+    }
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+// 拆分参数，支持双引号
+fn split_args(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_token = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+// 解析一行 "Key value" 或 "Key=value"，关键字统一为小写
+fn parse_line(raw: &str) -> Option<(String, Vec<String>)> {
+    let line = raw.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let split = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let key = line[..split].to_lowercase();
+    let rest = line[split..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    Some((key, split_args(rest)))
+}
+
+// 展开 Include 参数（相对路径相对于 ~/.ssh，文件名部分支持通配符）
+fn expand_include(pattern: &str) -> Vec<PathBuf> {
+    let path = if pattern.starts_with('~') || Path::new(pattern).is_absolute() {
+        expand_tilde(pattern)
+    } else {
+        home_dir().join(".ssh").join(pattern)
+    };
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !name.contains('*') && !name.contains('?') {
+        return vec![path];
+    }
+    let dir = match path.parent() {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+    let mut matches: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|e| match_wildcard(&name, &e.file_name().to_string_lossy()))
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    matches.sort();
+    matches
+}
+
+// 读取配置文件，Include 按出现位置展开；Match 块整体跳过
+fn parse_config_file(path: &Path, blocks: &mut Vec<HostBlock>, warnings: &mut Vec<String>, depth: usize) {
+    if depth > MAX_INCLUDE_DEPTH {
+        warnings.push(format!("Include 嵌套过深: {}", path.display()));
+        return;
+    }
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            warnings.push(format!("读取 {} 失败: {}", path.display(), e));
+            return;
+        }
+    };
+    for raw in content.lines() {
+        let (key, args) = match parse_line(raw) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+        match key.as_str() {
+            "host" => blocks.push(HostBlock { patterns: args, options: Vec::new() }),
+            "match" => {
+                // 不支持 Match 条件，之后的选项在下一个 Host 之前都忽略
+                warnings.push(format!("忽略 Match 块: {}", args.join(" ")));
+                blocks.push(HostBlock { patterns: Vec::new(), options: Vec::new() });
+            }
+            "include" => {
+                for pattern in &args {
+                    for included in expand_include(pattern) {
+                        parse_config_file(&included, blocks, warnings, depth + 1);
+                    }
+                }
+            }
+            _ => {
+                if blocks.is_empty() {
+                    // 第一个 Host 之前的选项对所有主机生效
+                    blocks.push(HostBlock { patterns: vec!["*".to_string()], options: Vec::new() });
+                }
+                if let Some(block) = blocks.last_mut() {
+                    block.options.push((key, args));
+                }
+            }
+        }
+    }
+}
+
+fn is_wildcard(pattern: &str) -> bool {
+    pattern.contains('*') || pattern.contains('?') || pattern.starts_with('!')
+}
+
+// Host 模式是否匹配别名（支持 ! 否定）
+fn block_matches(block: &HostBlock, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in &block.patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) => {
+                if match_wildcard(negated, alias) {
+                    return false;
+                }
+            }
+            None => {
+                if match_wildcard(pattern, alias) {
+                    matched = true;
+                }
+            }
+        }
+    }
+    matched
+}
+
+// 计算别名的有效选项：按文件顺序，每个选项取第一次出现的值
+fn resolve_options(blocks: &[HostBlock], alias: &str) -> HashMap<String, Vec<String>> {
+    let mut resolved: HashMap<String, Vec<String>> = HashMap::new();
+    for block in blocks.iter().filter(|b| block_matches(b, alias)) {
+        for (key, args) in &block.options {
+            resolved.entry(key.clone()).or_insert_with(|| args.clone());
+        }
+    }
+    resolved
+}
+
+fn first_value(options: &HashMap<String, Vec<String>>, key: &str) -> Option<String> {
+    options.get(key).and_then(|args| args.first().cloned())
+}
+
+// 展开 %h %r %u %d %% 等记号
+fn expand_tokens(value: &str, host: &str, user: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => out.push_str(host),
+            Some('r') => out.push_str(user),
+            Some('u') => out.push_str(&local_user()),
+            Some('d') => out.push_str(&home_dir().to_string_lossy()),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+// 解析 ProxyJump 的值
+fn parse_jumps(value: &str) -> Vec<JumpSpec> {
+    if value.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    value
+        .split(',')
+        .map(|s| s.trim().trim_start_matches("ssh://"))
+        .filter(|s| !s.is_empty())
+        .map(|spec| {
+            let (user, rest) = match spec.rsplit_once('@') {
+                Some((user, rest)) => (Some(user.to_string()), rest),
+                None => (None, spec),
+            };
+            let (host, port) = match rest.rsplit_once(':') {
+                Some((host, port)) if !host.contains(':') || host.starts_with('[') => {
+                    (host.trim_matches(|c| c == '[' || c == ']').to_string(), port.parse().ok())
+                }
+                _ => (rest.to_string(), None),
+            };
+            JumpSpec { user, host, port }
+        })
+        .collect()
+}
+
+// 配置ID只保留文件名安全的字符
+fn profile_id_for(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    format!("{}{}", IMPORTED_PREFIX, safe)
+}
+
+// 配置文件中指定的保活与超时设置，未指定的字段为 None
+#[derive(Default)]
+struct ConfigTimeouts {
+    keepalive_interval: Option<u64>,
+    keepalive_max: Option<usize>,
+    connect_timeout: Option<u64>,
+}
+
+impl ConfigTimeouts {
+    // 只覆盖配置文件中指定的字段
+    fn apply(&self, timeouts: &ConnectionTimeouts) -> ConnectionTimeouts {
+        let mut timeouts = timeouts.clone();
+        if let Some(v) = self.keepalive_interval {
+            timeouts.keepalive_interval = v;
+        }
+        if let Some(v) = self.keepalive_max {
+            timeouts.keepalive_max = v;
+        }
+        if let Some(v) = self.connect_timeout {
+            timeouts.connect_timeout = v;
+        }
+        timeouts
+    }
+}
+
+// 由 ssh config 生成的配置
+struct ImportedProfile {
+    alias: String,
+    profile: SshProfileMeta,
+    timeouts: ConfigTimeouts,
+    warnings: Vec<String>,
+}
+
+// 解析结果的构建器：为别名和 ProxyJump 中的临时主机生成配置
+struct Importer<'a> {
+    blocks: &'a [HostBlock],
+    aliases: HashSet<String>,
+    profiles: Vec<ImportedProfile>,
+    resolving: HashSet<String>,
+}
+
+impl<'a> Importer<'a> {
+    // 生成（或复用）指定名称的配置，返回配置ID
+    fn profile_for(&mut self, name: &str, user_override: Option<&str>, port_override: Option<u16>) -> String {
+        let options = resolve_options(self.blocks, name);
+        let user = user_override
+            .map(|u| u.to_string())
+            .or_else(|| first_value(&options, "user"))
+            .unwrap_or_else(local_user);
+        let port = port_override
+            .or_else(|| first_value(&options, "port").and_then(|p| p.parse().ok()))
+            .unwrap_or(22);
+        let host = first_value(&options, "hostname")
+            .map(|h| expand_tokens(&h, name, &user))
+            .unwrap_or_else(|| name.to_string());
+
+        // 别名直接用别名作ID；临时主机（带用户或端口覆盖）用完整地址作ID
+        let display = if self.aliases.contains(name) && user_override.is_none() && port_override.is_none() {
+            name.to_string()
+        } else {
+            format!("{}@{}:{}", user, name, port)
+        };
+        let id = profile_id_for(&display);
+        if self.profiles.iter().any(|p| p.profile.id == id) || self.resolving.contains(&id) {
+            return id;
+        }
+        self.resolving.insert(id.clone());
+
+        let mut warnings = Vec::new();
+        let private_key = options
+            .get("identityfile")
+            .and_then(|files| files.first())
+            .map(|file| expand_tilde(&expand_tokens(file, &host, &user)).to_string_lossy().to_string());
+        if options.contains_key("proxycommand") {
+            warnings.push("不支持 ProxyCommand，已忽略".to_string());
+        }
+
+        // ProxyJump 展开：跳板机自身的 ProxyJump 放在它之前
+        let mut jump_hosts = Vec::new();
+        if let Some(value) = first_value(&options, "proxyjump") {
+            for jump in parse_jumps(&value) {
+                let jump_id = self.profile_for(&jump.host, jump.user.as_deref(), jump.port);
+                if jump_id == id {
+                    warnings.push("ProxyJump 引用了自身，已忽略".to_string());
+                    continue;
+                }
+                if let Some(jump) = self.profiles.iter().find(|p| p.profile.id == jump_id) {
+                    for nested in &jump.profile.jump_hosts {
+                        if !jump_hosts.contains(nested) && nested != &id {
+                            jump_hosts.push(nested.clone());
+                        }
+                    }
+                }
+                if !jump_hosts.contains(&jump_id) {
+                    jump_hosts.push(jump_id);
+                }
+            }
+        }
+
+        // ServerAliveInterval / ServerAliveCountMax / ConnectTimeout 映射到保活与超时设置
+        let timeouts = ConfigTimeouts {
+            keepalive_interval: first_value(&options, "serveraliveinterval").and_then(|v| v.parse().ok()),
+            keepalive_max: first_value(&options, "serveralivecountmax").and_then(|v| v.parse().ok()),
+            connect_timeout: first_value(&options, "connecttimeout").and_then(|v| v.parse().ok()),
+        };
+
+        let profile = SshProfileMeta {
+            id: id.clone(),
+            host,
+            port,
+            username: user,
+            private_key,
+            name: Some(display.clone()),
+            group: Some(IMPORTED_GROUP.to_string()),
+            tags: vec!["ssh_config".to_string()],
+            use_agent: true,
+            jump_hosts,
+            timeouts: timeouts.apply(&ConnectionTimeouts::default()),
+            ..Default::default()
+        };
+        self.resolving.remove(&id);
+        self.profiles.push(ImportedProfile { alias: display, profile, timeouts, warnings });
+        id
+    }
+}
+
+// 解析 ssh config，生成所有具体 Host 别名（以及 ProxyJump 引用的主机）的配置
+fn build_profiles(path: &Path) -> (Vec<ImportedProfile>, Vec<String>) {
+    let mut blocks = Vec::new();
+    let mut warnings = Vec::new();
+    parse_config_file(path, &mut blocks, &mut warnings, 0);
+
+    let mut aliases = Vec::new();
+    for block in &blocks {
+        for pattern in &block.patterns {
+            if !is_wildcard(pattern) && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+    }
+
+    let mut importer = Importer {
+        blocks: &blocks,
+        aliases: aliases.iter().cloned().collect(),
+        profiles: Vec::new(),
+        resolving: HashSet::new(),
+    };
+    for alias in &aliases {
+        importer.profile_for(alias, None, None);
+    }
+    (importer.profiles, warnings)
+}
+
+// 导入关注的字段是否一致（保活与超时设置单独比较）
+fn same_connection(a: &SshProfileMeta, b: &SshProfileMeta) -> bool {
+    a.host == b.host
+        && a.port == b.port
+        && a.username == b.username
+        && a.private_key == b.private_key
+        && a.jump_hosts == b.jump_hosts
+}
+
+// 生成导入计划：与已有配置比较，prune 时列出配置文件中已不存在的导入配置
+fn plan_import(app: &AppHandle, path: Option<String>, prune: bool) -> Result<Vec<SshConfigImportItem>, String> {
+    let path = path
+        .map(|p| expand_tilde(&p))
+        .unwrap_or_else(|| home_dir().join(".ssh").join("config"));
+    if !path.exists() {
+        return Err(format!("配置文件不存在: {}", path.display()));
+    }
+
+    let existing: HashMap<String, SshProfileMeta> = crate::ssh::list_ssh_profiles(app.clone())?
+        .into_iter()
+        .map(|p| (p.id.clone(), p))
+        .collect();
+    let (profiles, file_warnings) = build_profiles(&path);
+    for warning in &file_warnings {
+        println!("ssh config: {}", warning);
+    }
+
+    let mut plan = Vec::new();
+    let mut seen = HashSet::new();
+    for ImportedProfile { alias, profile, timeouts, warnings } in profiles {
+        seen.insert(profile.id.clone());
+        let (action, profile) = match existing.get(&profile.id) {
+            None => ("create", profile),
+            Some(current) => {
+                // 保留用户在应用中修改的其他设置；超时只更新配置文件中指定的字段
+                let merged_timeouts = timeouts.apply(&current.timeouts);
+                if same_connection(current, &profile) && merged_timeouts == current.timeouts {
+                    ("unchanged", current.clone())
+                } else {
+                    let mut merged = current.clone();
+                    merged.host = profile.host;
+                    merged.port = profile.port;
+                    merged.username = profile.username;
+                    merged.private_key = profile.private_key;
+                    merged.jump_hosts = profile.jump_hosts;
+                    merged.timeouts = merged_timeouts;
+                    ("update", merged)
+                }
+            }
+        };
+        plan.push(SshConfigImportItem { action: action.to_string(), alias, profile, warnings });
+    }
+
+    if prune {
+        for (id, profile) in existing {
+            if id.starts_with(IMPORTED_PREFIX) && !seen.contains(&id) {
+                plan.push(SshConfigImportItem {
+                    action: "remove".to_string(),
+                    alias: profile.name.clone().unwrap_or_else(|| id.clone()),
+                    profile,
+                    warnings: Vec::new(),
+                });
+            }
+        }
+    }
+    Ok(plan)
+}
+
+// 预览导入结果（不写入任何文件）
+#[tauri::command]
+pub fn preview_ssh_config_import(
+    app: AppHandle,
+    path: Option<String>,
+    prune: Option<bool>,
+) -> Result<Vec<SshConfigImportItem>, String> {
+    plan_import(&app, path, prune.unwrap_or(false))
+}
+
+// 导入/同步 ssh config；prune 为 true 时删除配置文件中已不存在的导入配置
+#[tauri::command]
+pub fn import_ssh_config(
+    app: AppHandle,
+    path: Option<String>,
+    prune: Option<bool>,
+) -> Result<Vec<SshConfigImportItem>, String> {
+    let plan = plan_import(&app, path, prune.unwrap_or(false))?;
+    for item in &plan {
+        match item.action.as_str() {
+            "create" | "update" => crate::ssh::store_ssh_profile(&app, &item.profile)?,
+            "remove" => crate::ssh::delete_ssh_profile(app.clone(), item.profile.id.clone())?,
+            _ => {}
+        }
+    }
+    println!("ssh config 导入完成: {} 项", plan.len());
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 在临时目录中写入配置文件，返回目录
+    fn write_config(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("termlink-sshcfg-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content.replace("$DIR", &dir.to_string_lossy())).unwrap();
+        }
+        dir
+    }
+
+    fn profiles(name: &str, config: &str) -> Vec<ImportedProfile> {
+        let dir = write_config(name, &[("config", config)]);
+        build_profiles(&dir.join("config")).0
+    }
+
+    fn find<'a>(profiles: &'a [ImportedProfile], alias: &str) -> &'a SshProfileMeta {
+        &profiles.iter().find(|p| p.alias == alias).unwrap_or_else(|| panic!("missing {}", alias)).profile
+    }
+
+    #[test]
+    fn parse_line_forms() {
+        let cases = [
+            ("HostName example.com", Some(("hostname", vec!["example.com"]))),
+            ("  Port=2222", Some(("port", vec!["2222"]))),
+            ("User = admin", Some(("user", vec!["admin"]))),
+            ("IdentityFile \"~/my keys/id\"", Some(("identityfile", vec!["~/my keys/id"]))),
+            ("# comment", None),
+            ("", None),
+        ];
+        for (line, expected) in cases {
+            let expected = expected.map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect::<Vec<_>>()));
+            assert_eq!(parse_line(line), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn first_value_wins() {
+        let config = "\
+Host web
+    HostName web.example.com
+    User deploy
+Host web
+    User other
+    Port 2200
+Host *
+    User fallback
+    Port 22
+";
+        let profiles = profiles("precedence", config);
+        let web = find(&profiles, "web");
+        assert_eq!(web.host, "web.example.com");
+        assert_eq!(web.username, "deploy");
+        assert_eq!(web.port, 2200);
+    }
+
+    #[test]
+    fn options_before_first_host_apply_to_all() {
+        let profiles = profiles("global", "User everyone\nHost a\n    Port 2022\n");
+        let a = find(&profiles, "a");
+        assert_eq!(a.username, "everyone");
+        assert_eq!(a.port, 2022);
+        assert_eq!(a.host, "a");
+    }
+
+    #[test]
+    fn negated_host_patterns() {
+        let config = "\
+Host prod-* !prod-db
+    User ops
+Host prod-web prod-db
+    Port 22
+";
+        let profiles = profiles("negate", config);
+        assert_eq!(find(&profiles, "prod-web").username, "ops");
+        assert_ne!(find(&profiles, "prod-db").username, "ops");
+        // 通配符和否定模式不生成配置
+        assert_eq!(profiles.len(), 2);
+    }
+
+    #[test]
+    fn match_blocks_are_skipped() {
+        let config = "\
+Host a
+    User first
+Match host a exec \"true\"
+    User matched
+    Port 2222
+Host b
+    User second
+";
+        let dir = write_config("match", &[("config", config)]);
+        let (profiles, warnings) = build_profiles(&dir.join("config"));
+        let a = find(&profiles, "a");
+        assert_eq!(a.username, "first");
+        assert_eq!(a.port, 22);
+        assert_eq!(find(&profiles, "b").username, "second");
+        assert!(warnings.iter().any(|w| w.contains("Match")));
+    }
+
+    #[test]
+    fn include_expands_in_place() {
+        let dir = write_config(
+            "include",
+            &[("config", "Host a\n    User main\nInclude $DIR/conf.d/*.conf\nHost *\n    Port 2022\n")],
+        );
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+        fs::write(dir.join("conf.d/1.conf"), "Host b\n    HostName b.example.com\n").unwrap();
+        fs::write(dir.join("conf.d/2.conf"), "Host a b\n    Port 2200\n    User included\n").unwrap();
+        fs::write(dir.join("conf.d/ignored.txt"), "Host c\n").unwrap();
+        let (profiles, warnings) = build_profiles(&dir.join("config"));
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let a = find(&profiles, "a");
+        assert_eq!((a.username.as_str(), a.port), ("main", 2200));
+        let b = find(&profiles, "b");
+        assert_eq!((b.host.as_str(), b.username.as_str(), b.port), ("b.example.com", "included", 2200));
+        assert!(profiles.iter().all(|p| p.alias != "c"));
+    }
+
+    #[test]
+    fn include_cycle_is_bounded() {
+        let dir = write_config("cycle", &[("config", "Host a\nInclude $DIR/config\n")]);
+        let (profiles, warnings) = build_profiles(&dir.join("config"));
+        assert_eq!(profiles.len(), 1);
+        assert!(warnings.iter().any(|w| w.contains("Include")));
+    }
+
+    #[test]
+    fn token_expansion() {
+        let cases = [
+            ("%h.internal", "web.internal"),
+            ("%r@%h", "deploy@web"),
+            ("100%%", "100%"),
+            ("%x%", "%x%"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(expand_tokens(value, "web", "deploy"), expected, "{}", value);
+        }
+        let profiles = profiles("tokens", "Host web\n    HostName %h.example.com\n    User deploy\n    IdentityFile /keys/%r-%h\n");
+        let web = find(&profiles, "web");
+        assert_eq!(web.host, "web.example.com");
+        assert_eq!(web.private_key.as_deref(), Some("/keys/deploy-web.example.com"));
+    }
+
+    #[test]
+    fn proxy_jump_specs() {
+        let jumps = parse_jumps("alice@bastion:2222, ssh://gw,[::1]:22");
+        let parsed: Vec<_> = jumps.iter().map(|j| (j.user.as_deref(), j.host.as_str(), j.port)).collect();
+        assert_eq!(parsed, [(Some("alice"), "bastion", Some(2222)), (None, "gw", None), (None, "::1", Some(22))]);
+        assert!(parse_jumps("none").is_empty());
+    }
+
+    #[test]
+    fn proxy_jump_expansion() {
+        let config = "\
+Host app
+    ProxyJump inner
+Host inner
+    ProxyJump outer
+Host outer
+    HostName outer.example.com
+Host db
+    ProxyJump ops@outer:2222,app
+Host loop
+    ProxyJump loop
+";
+        let profiles = profiles("jump", config);
+        // 跳板机自身的跳板放在它之前
+        assert_eq!(find(&profiles, "app").jump_hosts, [profile_id_for("outer"), profile_id_for("inner")]);
+        // 带用户和端口的临时主机单独生成配置
+        let adhoc = find(&profiles, "ops@outer:2222");
+        assert_eq!((adhoc.host.as_str(), adhoc.username.as_str(), adhoc.port), ("outer.example.com", "ops", 2222));
+        assert_eq!(
+            find(&profiles, "db").jump_hosts,
+            [profile_id_for("ops@outer:2222"), profile_id_for("outer"), profile_id_for("inner"), profile_id_for("app")]
+        );
+        let looped = profiles.iter().find(|p| p.alias == "loop").unwrap();
+        assert!(looped.profile.jump_hosts.is_empty());
+        assert!(!looped.warnings.is_empty());
+    }
+
+    #[test]
+    fn only_configured_timeouts_are_applied() {
+        let config = "\
+Host a
+    ServerAliveInterval 10
+Host b
+    ConnectTimeout 5
+    ServerAliveCountMax 7
+Host c
+";
+        let profiles = profiles("timeouts", config);
+        let current = ConnectionTimeouts {
+            keepalive_interval: 60,
+            keepalive_max: 9,
+            connect_timeout: 40,
+            inactivity_timeout: 0,
+        };
+        let cases = [("a", (10, 9, 40)), ("b", (60, 7, 5)), ("c", (60, 9, 40))];
+        for (alias, (interval, max, connect)) in cases {
+            let imported = profiles.iter().find(|p| p.alias == alias).unwrap();
+            let merged = imported.timeouts.apply(&current);
+            assert_eq!(
+                (merged.keepalive_interval, merged.keepalive_max, merged.connect_timeout, merged.inactivity_timeout),
+                (interval, max, connect, 0),
+                "{}",
+                alias
+            );
+        }
+        // 新导入的配置以默认值为基础
+        let defaults = ConnectionTimeouts::default();
+        assert_eq!(find(&profiles, "a").timeouts.keepalive_interval, 10);
+        assert_eq!(find(&profiles, "a").timeouts.connect_timeout, defaults.connect_timeout);
+    }
+}
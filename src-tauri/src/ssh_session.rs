@@ -0,0 +1,453 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use russh::*;
+use russh_keys::*;
+use russh_sftp::client::SftpSession;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tauri::AppHandle;
+use crate::ssh::{ConnectionTimeouts, SshProfileMeta};
+use crate::ssh_auth::AuthOptions;
+
+// 处理器编号，用于把远程转发（-R）的入站通道路由到对应的连接
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+// SSH客户端处理器（终端、SFTP、系统监控共用）
+pub(crate) struct Client {
+    id: u64,
+    host: String,
+    port: u16,
+}
+
+impl Client {
+    fn new(host: &str, port: u16) -> Self {
+        Client {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            host: host.to_string(),
+            port,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl client::Handler for Client {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        self,
+        server_public_key: &key::PublicKey,
+    ) -> Result<(Self, bool), Self::Error> {
+        // 通过 known_hosts 校验服务器密钥
+        let trusted = crate::known_hosts::verify_server_key(&self.host, self.port, server_public_key);
+        Ok((self, trusted))
+    }
+
+    // 服务器为远程转发（-R）打开的通道
+    async fn server_channel_open_forwarded_tcpip(
+        self,
+        channel: Channel<client::Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        session: client::Session,
+    ) -> Result<(Self, client::Session), Self::Error> {
+        crate::port_forward::accept_forwarded_channel(
+            self.id,
+            channel,
+            connected_address,
+            connected_port,
+            originator_address,
+            originator_port,
+        );
+        Ok((self, session))
+    }
+}
+
+// 一个已认证的SSH连接，终端、SFTP和系统监控在其上各自打开通道
+pub struct SshConnection {
+    pub key: String,
+    pub profile_id: Option<String>,
+    // 目标连接处理器的编号
+    pub client_id: u64,
+    handle: tokio::sync::Mutex<client::Handle<Client>>,
+    // 跳板机连接（按连接顺序），需要与目标连接同生命周期
+    jumps: Vec<client::Handle<Client>>,
+    consumers: Mutex<HashSet<String>>,
+}
+
+// 每个连接键一个槽位，保证同一主机同时只进行一次连接和认证
+type ConnectionSlot = Arc<tokio::sync::Mutex<Option<Arc<SshConnection>>>>;
+
+static CONNECTION_SLOTS: Lazy<Mutex<HashMap<String, ConnectionSlot>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 已建立的连接（连接键 -> 连接）
+static ACTIVE_CONNECTIONS: Lazy<Mutex<HashMap<String, Arc<SshConnection>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 使用者（terminal:{id} / sftp:{id} / monitor:{id}）-> 连接键
+static CONSUMERS: Lazy<Mutex<HashMap<String, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 建立连接所需的参数
+#[derive(Clone)]
+pub struct ConnectRequest {
+    pub host: String,
+    pub port: u16,
+    pub auth: AuthOptions,
+    pub profile_id: Option<String>,
+}
+
+impl ConnectRequest {
+    // 有配置ID时按配置复用连接，否则按 user@host:port 复用
+    fn key(&self) -> String {
+        match &self.profile_id {
+            Some(id) => format!("profile:{}", id),
+            None => format!("{}@{}:{}", self.auth.username, self.host, self.port),
+        }
+    }
+}
+
+// 使用者ID，避免终端、SFTP、监控使用相同ID时互相覆盖
+pub fn terminal_consumer(id: &str) -> String {
+    format!("terminal:{}", id)
+}
+
+pub fn sftp_consumer(id: &str) -> String {
+    format!("sftp:{}", id)
+}
+
+pub fn monitor_consumer(id: &str) -> String {
+    format!("monitor:{}", id)
+}
+
+impl SshConnection {
+    // 打开一个会话通道（shell / exec / subsystem）
+    pub async fn open_session_channel(&self) -> Result<Channel<client::Msg>, String> {
+        let handle = self.handle.lock().await;
+        handle
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("创建通道失败: {}", e))
+    }
+
+    // 在新的 exec 通道中执行命令并返回输出
+    pub async fn exec(&self, command: &str) -> Result<String, String> {
+        let mut channel = self.open_session_channel().await?;
+
+        if let Err(e) = channel.exec(true, command.as_bytes()).await {
+            return Err(format!("执行命令失败: {}", e));
+        }
+
+        // 读取输出
+        let mut output = Vec::new();
+        let mut code = None;
+
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Data { data } => {
+                    output.extend_from_slice(&data);
+                },
+                ChannelMsg::ExitStatus { exit_status } => {
+                    code = Some(exit_status);
+                },
+                ChannelMsg::Eof => {
+                    break;
+                },
+                _ => {}
+            }
+        }
+
+        if code == Some(0) || code.is_none() {
+            Ok(String::from_utf8_lossy(&output).trim().to_string())
+        } else {
+            Err(format!("命令执行失败，退出码: {:?}", code))
+        }
+    }
+
+    // 在连接上打开 SFTP 子系统
+    pub async fn open_sftp(&self) -> Result<SftpSession, String> {
+        let channel = self.open_session_channel().await?;
+
+        if let Err(e) = channel.request_subsystem(true, "sftp").await {
+            return Err(format!("请求SFTP子系统失败: {}", e));
+        }
+
+        SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| format!("创建SFTP会话失败: {}", e))
+    }
+
+    // 打开 direct-tcpip 通道（本地转发 / SOCKS 代理）
+    pub async fn open_direct_tcpip(
+        &self,
+        host: &str,
+        port: u16,
+        originator_address: &str,
+        originator_port: u16,
+    ) -> Result<Channel<client::Msg>, String> {
+        let handle = self.handle.lock().await;
+        handle
+            .channel_open_direct_tcpip(host, port as u32, originator_address, originator_port as u32)
+            .await
+            .map_err(|e| format!("打开转发通道 {}:{} 失败: {}", host, port, e))
+    }
+
+    // 请求服务器监听远程端口（-R）
+    pub async fn tcpip_forward(&self, address: &str, port: u16) -> Result<(), String> {
+        let mut handle = self.handle.lock().await;
+        handle
+            .tcpip_forward(address, port as u32)
+            .await
+            .map_err(|e| format!("请求远程转发 {}:{} 失败: {}", address, port, e))?;
+        Ok(())
+    }
+
+    // 取消远程端口监听
+    pub async fn cancel_tcpip_forward(&self, address: &str, port: u16) -> Result<(), String> {
+        let handle = self.handle.lock().await;
+        handle
+            .cancel_tcpip_forward(address, port as u32)
+            .await
+            .map_err(|e| format!("取消远程转发 {}:{} 失败: {}", address, port, e))?;
+        Ok(())
+    }
+
+    pub async fn is_closed(&self) -> bool {
+        self.handle.lock().await.is_closed()
+    }
+
+    async fn disconnect(&self) {
+        let handle = self.handle.lock().await;
+        let _ = handle.disconnect(Disconnect::ByApplication, "", "").await;
+        for jump in self.jumps.iter().rev() {
+            let _ = jump.disconnect(Disconnect::ByApplication, "", "").await;
+        }
+    }
+}
+
+// 打开到 host:port 的SSH传输：直接TCP连接，或经由上一跳的 direct-tcpip 通道
+async fn open_transport(
+    app: &AppHandle,
+    timeouts: &ConnectionTimeouts,
+    via: Option<&client::Handle<Client>>,
+    host: &str,
+    port: u16,
+) -> Result<(client::Handle<Client>, u64), String> {
+    let config = client_config(timeouts);
+    let handler = Client::new(host, port);
+    let client_id = handler.id;
+    let transport = async {
+        let result = match via {
+            None => client::connect(config, (host, port), handler).await,
+            Some(jump) => {
+                let channel = jump
+                    .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+                    .await
+                    .map_err(|e| format!("通过跳板机连接 {}:{} 失败: {}", host, port, e))?;
+                client::connect_stream(config, channel.into_stream(), handler).await
+            }
+        };
+        result
+            .map(|handle| (handle, client_id))
+            .map_err(|e| crate::known_hosts::describe_connect_error(app, host, port, e))
+    };
+    match timeouts.connect_timeout() {
+        Some(limit) => tokio::time::timeout(limit, transport)
+            .await
+            .map_err(|_| format!("连接 {}:{} 超时（{}秒）", host, port, limit.as_secs()))?,
+        None => transport.await,
+    }
+}
+
+// 按配置的保活与超时设置构建客户端配置
+fn client_config(timeouts: &ConnectionTimeouts) -> Arc<client::Config> {
+    Arc::new(client::Config {
+        inactivity_timeout: timeouts.inactivity_timeout(),
+        keepalive_interval: timeouts.keepalive_interval(),
+        keepalive_max: timeouts.keepalive_max,
+        ..<_>::default()
+    })
+}
+
+// 目标连接的保活与超时设置（无配置时使用默认值）
+fn request_timeouts(app: &AppHandle, request: &ConnectRequest) -> ConnectionTimeouts {
+    request
+        .profile_id
+        .as_deref()
+        .and_then(|id| crate::ssh::load_ssh_profile(app, id).ok())
+        .map(|profile| profile.timeouts)
+        .unwrap_or_default()
+}
+
+// 读取目标配置中的跳板机列表
+fn load_jump_profiles(app: &AppHandle, request: &ConnectRequest) -> Result<Vec<SshProfileMeta>, String> {
+    let profile_id = match &request.profile_id {
+        Some(id) => id,
+        None => return Ok(Vec::new()),
+    };
+    let profile = match crate::ssh::load_ssh_profile(app, profile_id) {
+        Ok(profile) => profile,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut jumps = Vec::new();
+    for jump_id in &profile.jump_hosts {
+        if jump_id == profile_id {
+            return Err("跳板机配置不能引用自身".to_string());
+        }
+        let jump = crate::ssh::load_ssh_profile(app, jump_id)
+            .map_err(|_| format!("跳板机配置不存在: {}", jump_id))?;
+        jumps.push(jump);
+    }
+    Ok(jumps)
+}
+
+// 跳板机的认证参数：使用其配置中的私钥和已保存的密码，提示沿用目标连接的通道
+fn jump_auth(app: &AppHandle, jump: &SshProfileMeta, request: &ConnectRequest) -> AuthOptions {
+    let password = if jump.save_password {
+        crate::ssh::get_ssh_password(jump.id.clone()).ok().flatten()
+    } else {
+        None
+    };
+    let mut auth = AuthOptions::resolve(app, &jump.username, password, None, None, Some(&jump.id));
+    auth.prompter = request.auth.prompter.clone();
+    auth
+}
+
+// 建立并认证新的SSH连接
+async fn connect(app: &AppHandle, key: &str, request: &ConnectRequest) -> Result<SshConnection, String> {
+    println!("建立SSH连接: {}@{}:{}", request.auth.username, request.host, request.port);
+    println!("  认证方式: {}", request.auth.describe());
+
+    // 依次连接跳板机，每一跳都经由上一跳的 direct-tcpip 通道
+    let mut jumps: Vec<client::Handle<Client>> = Vec::new();
+    for jump in load_jump_profiles(app, request)? {
+        let label = jump.name.clone().unwrap_or_else(|| format!("{}@{}:{}", jump.username, jump.host, jump.port));
+        println!("连接跳板机: {}", label);
+        let (mut hop, _) = open_transport(app, &jump.timeouts, jumps.last(), &jump.host, jump.port).await?;
+        crate::ssh_auth::authenticate(&mut hop, &jump_auth(app, &jump, request))
+            .await
+            .map_err(|e| format!("跳板机 {} 认证失败: {}", label, e))?;
+        println!("✓ 跳板机连接成功: {}", label);
+        jumps.push(hop);
+    }
+
+    // 目标主机使用其配置中的保活与超时设置，跳板机使用各自的设置
+    let timeouts = request_timeouts(app, request);
+    let (mut session, client_id) = open_transport(app, &timeouts, jumps.last(), &request.host, request.port).await?;
+    println!("✓ SSH连接成功");
+
+    // 进行认证
+    crate::ssh_auth::authenticate(&mut session, &request.auth).await?;
+
+    Ok(SshConnection {
+        key: key.to_string(),
+        profile_id: request.profile_id.clone(),
+        client_id,
+        handle: tokio::sync::Mutex::new(session),
+        jumps,
+        consumers: Mutex::new(HashSet::new()),
+    })
+}
+
+// 获取（必要时建立）共享连接，并登记使用者
+pub async fn acquire(app: &AppHandle, consumer: &str, request: ConnectRequest) -> Result<Arc<SshConnection>, String> {
+    let key = request.key();
+
+    // 使用者之前挂在其他连接上时先释放
+    let previous = CONSUMERS.lock().get(consumer).cloned();
+    if previous.map(|k| k != key).unwrap_or(false) {
+        release(consumer).await;
+    }
+
+    let slot = CONNECTION_SLOTS
+        .lock()
+        .entry(key.clone())
+        .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(None)))
+        .clone();
+    let mut guard = slot.lock().await;
+
+    let existing = guard.clone();
+    let connection = match existing {
+        Some(conn) if !conn.is_closed().await => {
+            println!("✓ 复用SSH连接: {}", key);
+            conn
+        }
+        Some(stale) => {
+            // 旧连接已断开（如网络中断）：重新连接，沿用其使用者和端口转发
+            println!("SSH连接已断开，重新连接: {}", key);
+            let conn = Arc::new(connect(app, &key, &request).await?);
+            *conn.consumers.lock() = stale.consumers.lock().clone();
+            let forwards = crate::port_forward::stop_all(&stale).await;
+            *guard = Some(conn.clone());
+            ACTIVE_CONNECTIONS.lock().insert(key.clone(), conn.clone());
+            crate::port_forward::restore_forwards(&conn, forwards).await;
+            conn
+        }
+        None => {
+            let conn = Arc::new(connect(app, &key, &request).await?);
+            *guard = Some(conn.clone());
+            ACTIVE_CONNECTIONS.lock().insert(key.clone(), conn.clone());
+            // 启动配置中保存的端口转发
+            crate::port_forward::start_saved_forwards(app, &conn).await;
+            conn
+        }
+    };
+
+    connection.consumers.lock().insert(consumer.to_string());
+    CONSUMERS.lock().insert(consumer.to_string(), key);
+    Ok(connection)
+}
+
+// 登记使用者到已有连接（如从终端连接创建SFTP）
+pub fn attach(consumer: &str, connection: &Arc<SshConnection>) {
+    connection.consumers.lock().insert(consumer.to_string());
+    CONSUMERS.lock().insert(consumer.to_string(), connection.key.clone());
+}
+
+// 查找使用者当前使用的连接
+pub fn connection_for(consumer: &str) -> Option<Arc<SshConnection>> {
+    let key = CONSUMERS.lock().get(consumer).cloned()?;
+    let connection = ACTIVE_CONNECTIONS.lock().get(&key).cloned();
+    connection
+}
+
+// 按前端的连接ID（终端、SFTP或监控ID）查找连接
+pub fn find_connection(id: &str) -> Option<Arc<SshConnection>> {
+    connection_for(&terminal_consumer(id))
+        .or_else(|| connection_for(&sftp_consumer(id)))
+        .or_else(|| connection_for(&monitor_consumer(id)))
+}
+
+// 释放使用者；最后一个使用者离开时断开连接
+pub async fn release(consumer: &str) {
+    let key = match CONSUMERS.lock().remove(consumer) {
+        Some(key) => key,
+        None => return,
+    };
+    let slot = match CONNECTION_SLOTS.lock().get(&key).cloned() {
+        Some(slot) => slot,
+        None => return,
+    };
+
+    let mut guard = slot.lock().await;
+    let idle = match guard.as_ref() {
+        Some(conn) => {
+            let mut consumers = conn.consumers.lock();
+            consumers.remove(consumer);
+            consumers.is_empty()
+        }
+        None => false,
+    };
+
+    if idle {
+        if let Some(conn) = guard.take() {
+            ACTIVE_CONNECTIONS.lock().remove(&key);
+            crate::port_forward::stop_all(&conn).await;
+            conn.disconnect().await;
+            println!("SSH连接已断开（无使用者）: {}", key);
+        }
+    }
+}
@@ -1,47 +1,44 @@
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use std::{collections::HashMap, thread, sync::Arc};
-use tauri::Emitter;
-use tokio::runtime::Runtime;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
+use tokio::sync::mpsc;
 use russh::*;
-use russh_keys::*;
+use crate::ssh::{ReconnectPolicy, SshProfileMeta, TerminalOptions, Transport};
+use crate::ssh_session::{self, ConnectRequest, SshConnection};
+use crate::term_data::{input_bytes, FlowControl, OutputFramer};
 
 // SSH终端消息类型
 enum SshMsg {
-    Write(String),
+    Write(Vec<u8>),
     Resize { cols: u16, rows: u16 },
+    Reconnect,
     Close,
-    #[allow(dead_code)]
-    ExecuteCommand { command: String, response_sender: tokio::sync::oneshot::Sender<Result<String, String>> },
+}
+
+// 终端会话结束的原因
+enum SessionEnd {
+    // 用户关闭或远程shell正常退出
+    Closed,
+    // 连接中断，可以尝试重连
+    Lost(String),
+    // 用户请求重连，不受自动重连策略限制
+    Restart,
 }
 
 // SSH终端连接
 struct SshTerminal {
-    sender: crossbeam_channel::Sender<SshMsg>,
-    #[allow(dead_code)]
-    runtime: Option<Arc<Runtime>>,
+    sender: mpsc::UnboundedSender<SshMsg>,
+    // 原始字节模式：输出以 ssh_bytes://{id} 事件发送字节数组
+    binary: Arc<AtomicBool>,
 }
 
 // 全局SSH终端连接管理
 static SSH_TERMINALS: Lazy<Mutex<HashMap<String, SshTerminal>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-// SSH客户端处理器
-struct Client;
-
-#[async_trait::async_trait]
-impl client::Handler for Client {
-    type Error = russh::Error;
-
-    async fn check_server_key(
-        self,
-        _server_public_key: &key::PublicKey,
-    ) -> Result<(Self, bool), Self::Error> {
-        // 接受所有服务器密钥 (在生产环境中应该验证)
-        Ok((self, true))
-    }
-}
-
 // 启动SSH终端
 #[tauri::command]
 pub fn start_ssh_terminal(
@@ -51,31 +48,54 @@ pub fn start_ssh_terminal(
     port: u16,
     username: String,
     password: Option<String>,
+    private_key: Option<String>,
+    passphrase: Option<String>,
+    profile_id: Option<String>,
     cols: u16,
     rows: u16,
 ) -> Result<(), String> {
     println!("开始SSH连接: {}@{}:{}", username, host, port);
-    
+
+    // 组合认证参数（私钥、口令、ssh-agent）
+    let auth = crate::ssh_auth::AuthOptions::resolve(
+        window.app_handle(),
+        &username,
+        password,
+        private_key,
+        passphrase,
+        profile_id.as_deref(),
+    )
+    .with_prompts(window.app_handle(), &id);
+    let profile = profile_id
+        .as_deref()
+        .and_then(|pid| crate::ssh::load_ssh_profile(window.app_handle(), pid).ok());
+    // 无配置时使用默认的重连策略和终端选项
+    let settings = profile.clone().unwrap_or_default();
+
+    // 配置了始终录制时自动开始录制
+    if let Some(profile) = profile.as_ref().filter(|p| p.always_record) {
+        let title = profile.name.clone().unwrap_or_else(|| format!("{}@{}", username, host));
+        if let Err(e) = crate::recorder::start(&id, cols, rows, Some(title), false, None) {
+            println!("自动录制失败: {}", e);
+        }
+    }
+    let request = ConnectRequest { host: host.clone(), port, auth, profile_id };
+
     // 创建通信通道
-    let (tx, rx) = crossbeam_channel::unbounded::<SshMsg>();
-    
-    // 创建Tokio运行时
-    let runtime = match Runtime::new() {
-        Ok(rt) => Arc::new(rt),
-        Err(e) => return Err(format!("创建运行时失败: {}", e)),
-    };
-    
-    // 保存发送端和运行时
-    SSH_TERMINALS.lock().insert(id.clone(), SshTerminal {
-        sender: tx,
-        runtime: Some(runtime.clone()),
-    });
-    
-    // 在新线程中处理SSH连接
-    let runtime_clone = runtime.clone();
-    thread::spawn(move || {
-        // 在运行时中执行异步SSH连接
-        match runtime_clone.block_on(connect_ssh_russh(&window, &id, &host, port, &username, password, cols, rows, rx)) {
+    let (tx, rx) = mpsc::unbounded_channel::<SshMsg>();
+
+    // 保存发送端
+    let binary = Arc::new(AtomicBool::new(false));
+    let flow = crate::term_data::register_flow(&id);
+    SSH_TERMINALS.lock().insert(id.clone(), SshTerminal { sender: tx, binary: binary.clone() });
+
+    // 所有SSH连接都运行在 Tauri 的异步运行时上，以便终端、SFTP和监控共享同一连接
+    tauri::async_runtime::spawn(async move {
+        let result = match settings.transport {
+            Transport::Mosh => connect_mosh(&window, &id, request, settings, (cols, rows), rx, &binary, &flow).await,
+            Transport::Ssh => connect_ssh_russh(&window, &id, request, settings, cols, rows, rx, &binary, &flow).await,
+        };
+        match result {
             Ok(_) => {
                 println!("SSH连接关闭: {}@{}:{}", username, host, port);
                 let _ = window.emit(&format!("ssh_exit://{}", id), "");
@@ -86,267 +106,408 @@ pub fn start_ssh_terminal(
                 let _ = window.emit(&format!("ssh_exit://{}", id), "");
             }
         }
-        
-        // 连接结束后移除
+
+        // 连接结束后移除，并释放共享连接
         SSH_TERMINALS.lock().remove(&id);
+        crate::term_data::unregister_flow(&id, &flow);
+        crate::recorder::stop(&id);
+        crate::session_log::close(&id);
+        ssh_session::release(&ssh_session::terminal_consumer(&id)).await;
     });
-    
+
     Ok(())
 }
 
-// 使用russh连接到SSH服务器
+// 通过共享连接打开终端通道；连接中断时按重连策略在同一终端ID下重新建立
 async fn connect_ssh_russh(
     window: &tauri::Window,
     id: &str,
-    host: &str,
-    port: u16,
-    username: &str,
-    password: Option<String>,
+    request: ConnectRequest,
+    settings: SshProfileMeta,
     cols: u16,
     rows: u16,
-    rx: crossbeam_channel::Receiver<SshMsg>,
+    mut rx: mpsc::UnboundedReceiver<SshMsg>,
+    binary: &AtomicBool,
+    flow: &FlowControl,
 ) -> Result<(), String> {
-    println!("使用russh库连接SSH服务器...");
-    
-    // 输出连接信息
-    println!("SSH连接详情:");
-    println!("  目标主机: {}:{}", host, port);
-    println!("  用户名: {}", username);
-    println!("  认证方式: {}", if password.is_some() { "密码认证" } else { "密钥认证" });
-    
-    // 创建客户端配置
-    let config = client::Config {
-        inactivity_timeout: Some(std::time::Duration::from_secs(300)), // 5分钟超时
-        ..<_>::default()
-    };
-    
-    println!("连接到SSH服务器...");
-    
-    // 连接到服务器
-    let mut session = match client::connect(Arc::new(config), (host, port), Client).await {
-        Ok(session) => {
-            println!("✓ SSH连接成功");
-            session
-        },
-        Err(e) => {
-            return Err(format!("SSH连接失败: {}", e));
+    let policy = &settings.reconnect;
+    let consumer = ssh_session::terminal_consumer(id);
+    let mut size = (cols, rows);
+    let mut attempt: u32 = 0;
+    // 用户请求的重连
+    let mut restarting = false;
+
+    loop {
+        let reconnecting = attempt > 0 || restarting;
+        let result = match ssh_session::acquire(window.app_handle(), &consumer, request.clone()).await {
+            Ok(connection) => match open_shell(&connection, id, &settings, size, reconnecting).await {
+                Ok(channel) => {
+                    if reconnecting {
+                        println!("✓ SSH终端已重连: {}", id);
+                        let _ = window.emit(&format!("ssh_reconnected://{}", id), attempt);
+                    }
+                    attempt = 0;
+                    restarting = false;
+                    handle_russh_session(window, id, channel, &mut rx, &mut size, binary, flow).await
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+
+        let reason = match result {
+            Ok(SessionEnd::Closed) => return Ok(()),
+            Ok(SessionEnd::Restart) => {
+                ssh_session::release(&consumer).await;
+                attempt = 0;
+                restarting = true;
+                continue;
+            }
+            Ok(SessionEnd::Lost(reason)) => reason,
+            // 首次连接失败（认证失败、主机密钥变更等）直接返回错误
+            Err(e) if !reconnecting => return Err(e),
+            Err(e) => e,
+        };
+
+        if !policy.enabled || attempt >= policy.max_attempts {
+            return Err(format!("连接已断开: {}", reason));
         }
-    };
-    
-    // 进行认证
-    if let Some(pwd) = password {
-        println!("开始密码认证...");
-        match session.authenticate_password(username, &pwd).await {
-            Ok(true) => {
-                println!("✓ 密码认证成功");
+        attempt += 1;
+        let delay = policy.delay_for(attempt);
+        println!("SSH终端连接中断（{}），{}ms 后第 {} 次重连", reason, delay.as_millis(), attempt);
+        let _ = window.emit(&format!("ssh_reconnecting://{}", id), serde_json::json!({
+            "attempt": attempt,
+            "maxAttempts": policy.max_attempts,
+            "delayMs": delay.as_millis() as u64,
+            "reason": reason,
+        }));
+
+        // 释放已断开的连接，等待退避时间（期间仍响应关闭和调整大小）
+        ssh_session::release(&consumer).await;
+        match wait_for_retry(&mut rx, delay, &mut size, flow).await {
+            Some(SessionEnd::Closed) => return Ok(()),
+            // 用户请求立即重连，重新计算重连次数
+            Some(_) => {
+                attempt = 0;
+                restarting = true;
+            }
+            None => {}
+        }
+    }
+}
+
+// Mosh 传输：SSH连接只用于启动 mosh-server，之后由 mosh-client 经UDP连接
+// 网络切换由 mosh 自行恢复，不使用SSH的断线重连
+async fn connect_mosh(
+    window: &tauri::Window,
+    id: &str,
+    request: ConnectRequest,
+    settings: SshProfileMeta,
+    size: (u16, u16),
+    mut rx: mpsc::UnboundedReceiver<SshMsg>,
+    binary: &Arc<AtomicBool>,
+    flow: &Arc<FlowControl>,
+) -> Result<(), String> {
+    // 先做本地检查，避免远程启动的 mosh-server 无人连接
+    let client_path = crate::mosh::check_prerequisites(&settings)?;
+    let consumer = ssh_session::terminal_consumer(id);
+    let connection = ssh_session::acquire(window.app_handle(), &consumer, request.clone()).await?;
+    let connect = crate::mosh::bootstrap(&connection, &request.host, &settings, client_path).await;
+    drop(connection);
+    ssh_session::release(&consumer).await;
+    let connect = connect?;
+
+    let (mut client, mut exited) =
+        crate::mosh::MoshClient::spawn(window, id, &connect, &settings, size, binary.clone(), flow.clone())?;
+
+    let result = loop {
+        tokio::select! {
+            code = &mut exited => {
+                println!("mosh-client 退出，状态码: {:?}", code.ok().flatten());
+                break Ok(());
             },
-            Ok(false) => {
-                return Err("密码认证失败：用户名或密码错误".to_string());
+            msg = rx.recv() => {
+                match msg {
+                    Some(SshMsg::Write(data)) => {
+                        if let Err(e) = client.write(&data) {
+                            client.close(&mut exited).await;
+                            break Err(e);
+                        }
+                    },
+                    Some(SshMsg::Resize { cols, rows }) => client.resize(cols, rows),
+                    Some(SshMsg::Reconnect) => println!("Mosh 会话会自动恢复连接，忽略重连请求"),
+                    Some(SshMsg::Close) | None => {
+                        println!("收到关闭信号");
+                        client.close(&mut exited).await;
+                        break Ok(());
+                    },
+                }
+            },
+        }
+    };
+
+    // 唤醒可能因流控暂停的读取线程，再等待剩余输出发送完毕
+    crate::term_data::unregister_flow(id, flow);
+    client.finish().await;
+    result
+}
+
+// 等待重连；等待结束返回 None，用户在等待期间关闭终端或请求立即重连时返回对应的结束原因
+async fn wait_for_retry(
+    rx: &mut mpsc::UnboundedReceiver<SshMsg>,
+    delay: std::time::Duration,
+    size: &mut (u16, u16),
+    flow: &FlowControl,
+) -> Option<SessionEnd> {
+    let sleep = tokio::time::sleep(delay);
+    tokio::pin!(sleep);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return None,
+            msg = rx.recv() => match msg {
+                Some(SshMsg::Close) | None => return Some(SessionEnd::Closed),
+                Some(SshMsg::Resize { cols, rows }) => *size = (cols, rows),
+                Some(SshMsg::Reconnect) => return Some(SessionEnd::Restart),
+                // 断线期间的输入直接丢弃
+                Some(SshMsg::Write(data)) => flow.record_dropped(data.len()),
             },
-            Err(e) => {
-                return Err(format!("密码认证过程失败: {}", e));
-            }
         }
-    } else {
-        return Err("当前仅支持密码认证".to_string());
     }
-    
-    // 这里我们不需要克隆session，因为Handle不支持Clone
-    // 我们将采用不同的策略来支持系统监控
-    
+}
+
+// 重新附加远程 tmux/screen 会话的命令
+fn reattach_command(id: &str, policy: &ReconnectPolicy) -> Option<String> {
+    let name: String = policy
+        .session_name
+        .clone()
+        .unwrap_or_else(|| format!("termlink-{}", id))
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    match policy.reattach.as_deref() {
+        Some("tmux") => Some(format!("tmux new-session -A -s {}", name)),
+        Some("screen") => Some(format!("screen -D -RR {}", name)),
+        _ => None,
+    }
+}
+
+// 按名称解析终端模式（RFC 4254 第 8 节），未知名称忽略
+fn terminal_modes(options: &TerminalOptions) -> Vec<(Pty, u32)> {
+    let mut modes = Vec::new();
+    for (name, value) in &options.modes {
+        let mode = match name.to_ascii_uppercase().as_str() {
+            "VINTR" => Pty::VINTR,
+            "VQUIT" => Pty::VQUIT,
+            "VERASE" => Pty::VERASE,
+            "VKILL" => Pty::VKILL,
+            "VEOF" => Pty::VEOF,
+            "VEOL" => Pty::VEOL,
+            "VSTART" => Pty::VSTART,
+            "VSTOP" => Pty::VSTOP,
+            "VSUSP" => Pty::VSUSP,
+            "VWERASE" => Pty::VWERASE,
+            "VLNEXT" => Pty::VLNEXT,
+            "ICRNL" => Pty::ICRNL,
+            "IXON" => Pty::IXON,
+            "IXANY" => Pty::IXANY,
+            "IXOFF" => Pty::IXOFF,
+            "IMAXBEL" => Pty::IMAXBEL,
+            "IUTF8" => Pty::IUTF8,
+            "ISIG" => Pty::ISIG,
+            "ICANON" => Pty::ICANON,
+            "ECHO" => Pty::ECHO,
+            "ECHOE" => Pty::ECHOE,
+            "ECHOK" => Pty::ECHOK,
+            "ECHONL" => Pty::ECHONL,
+            "IEXTEN" => Pty::IEXTEN,
+            "ECHOCTL" => Pty::ECHOCTL,
+            "ECHOKE" => Pty::ECHOKE,
+            "OPOST" => Pty::OPOST,
+            "ONLCR" => Pty::ONLCR,
+            "CS7" => Pty::CS7,
+            "CS8" => Pty::CS8,
+            _ => {
+                println!("忽略未知的终端模式: {}", name);
+                continue;
+            }
+        };
+        modes.push((mode, *value));
+    }
+    modes
+}
+
+// 在连接上打开终端通道并启动shell
+async fn open_shell(
+    connection: &SshConnection,
+    id: &str,
+    settings: &SshProfileMeta,
+    (cols, rows): (u16, u16),
+    reconnecting: bool,
+) -> Result<Channel<client::Msg>, String> {
+    let options = &settings.terminal;
     println!("创建终端通道...");
-    
+
     // 创建通道
-    let channel = match session.channel_open_session().await {
-        Ok(channel) => {
-            println!("✓ 终端通道创建成功");
-            channel
-        },
-        Err(e) => {
-            return Err(format!("创建终端通道失败: {}", e));
+    let channel = connection.open_session_channel().await?;
+    println!("✓ 终端通道创建成功");
+
+    // 发送环境变量；服务器未允许的变量会被忽略，不影响连接
+    for (name, value) in options.environment() {
+        if let Err(e) = channel.set_env(false, name.as_str(), value.as_str()).await {
+            println!("设置环境变量 {} 失败: {}", name, e);
         }
-    };
-    
+    }
+
     // 请求PTY
-    if let Err(e) = channel.request_pty(true, "xterm-256color", cols as u32, rows as u32, 0, 0, &[]).await {
+    let modes = terminal_modes(options);
+    if let Err(e) = channel.request_pty(true, options.term(), cols as u32, rows as u32, 0, 0, &modes).await {
         return Err(format!("请求PTY失败: {}", e));
     }
-    
-    // 启动shell
-    if let Err(e) = channel.request_shell(true).await {
-        return Err(format!("启动shell失败: {}", e));
+
+    // 启动shell（配置了 tmux/screen 时附加到远程会话，断线重连后可恢复）
+    let reattach = reattach_command(id, &settings.reconnect);
+    let reattached = reattach.is_some() && reconnecting;
+    match reattach {
+        Some(command) => {
+            println!("附加远程会话: {}", command);
+            if let Err(e) = channel.exec(true, command.as_bytes()).await {
+                return Err(format!("附加远程会话失败: {}", e));
+            }
+        }
+        None => {
+            if let Err(e) = channel.request_shell(true).await {
+                return Err(format!("启动shell失败: {}", e));
+            }
+        }
+    }
+
+    // 执行启动命令；重新附加到已有的 tmux/screen 会话时不再重复执行
+    if let Some(command) = options.startup_command.as_ref().filter(|c| !c.trim().is_empty()) {
+        if !reattached {
+            let script = format!("{}\n", command.trim_end());
+            if let Err(e) = channel.data(script.as_bytes()).await {
+                println!("执行启动命令失败: {}", e);
+            }
+        }
     }
-    
+
     println!("✓ SSH终端启动成功");
-    
-    // 处理终端会话
-    handle_russh_session(window, id, session, channel, rx).await
+    Ok(channel)
 }
 
 // 处理russh会话
 async fn handle_russh_session(
     window: &tauri::Window,
     id: &str,
-    session: client::Handle<Client>,
     mut channel: Channel<client::Msg>,
-    rx: crossbeam_channel::Receiver<SshMsg>,
-) -> Result<(), String> {
-    use tokio::sync::mpsc;
-    
-    // 创建异步通道用于线程间通信
-    let (tx, mut async_rx) = mpsc::unbounded_channel::<SshMsg>();
-    
-    // 启动一个任务来监听同步通道并转发到异步通道
-    let tx_clone = tx.clone();
-    tokio::spawn(async move {
-        while let Ok(msg) = rx.recv() {
-            if tx_clone.send(msg).is_err() {
-                break;
-            }
-        }
-    });
-    
+    rx: &mut mpsc::UnboundedReceiver<SshMsg>,
+    size: &mut (u16, u16),
+    binary: &AtomicBool,
+    flow: &FlowControl,
+) -> Result<SessionEnd, String> {
+    // 输出按时间/大小合并成帧，跨数据块的多字节字符由解码器拼接
+    let mut framer = OutputFramer::new(id, "ssh_data", "ssh_bytes");
+
     // 主循环处理SSH消息
-    loop {
+    let end = loop {
+        let deadline = framer.deadline();
+        let paused = flow.is_paused();
         tokio::select! {
+            // 发送攒够时间的输出帧
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(std::time::Instant::now).into()), if deadline.is_some() => {
+                framer.flush(window, binary.load(Ordering::Relaxed), flow);
+            },
+
+            // 达到高水位时暂停读取通道，等待前端确认
+            _ = flow.wait(), if paused => {},
+
             // 处理来自通道的数据
-            Some(msg) = channel.wait() => {
+            msg = channel.wait(), if !paused => {
                 match msg {
-                    ChannelMsg::Data { data } => {
-                        let output = String::from_utf8_lossy(&data).to_string();
-                        let _ = window.emit(&format!("ssh_data://{}", id), output);
+                    Some(ChannelMsg::Data { data }) => {
+                        flow.record_read(data.len());
+                        framer.push(&data);
+                        if framer.is_full() {
+                            framer.flush(window, binary.load(Ordering::Relaxed), flow);
+                        }
                     },
-                    ChannelMsg::Eof => {
+                    Some(ChannelMsg::Eof) => {
                         println!("SSH通道EOF");
-                        break;
+                        break SessionEnd::Closed;
                     },
-                    ChannelMsg::Close => {
+                    Some(ChannelMsg::Close) => {
                         println!("SSH通道关闭");
-                        break;
+                        break SessionEnd::Closed;
                     },
-                    ChannelMsg::ExitStatus { exit_status } => {
+                    Some(ChannelMsg::ExitStatus { exit_status }) => {
                         println!("SSH进程退出，状态码: {}", exit_status);
-                        break;
+                        break SessionEnd::Closed;
                     },
-                    _ => {}
+                    Some(_) => {},
+                    // 通道被底层连接丢弃：网络中断或服务器断开
+                    None => break SessionEnd::Lost("SSH连接中断".to_string()),
                 }
             },
-            
+
             // 处理用户输入和控制消息
-            Some(msg) = async_rx.recv() => {
+            msg = rx.recv() => {
                 match msg {
-                    SshMsg::Write(data) => {
-                        if let Err(e) = channel.data(data.as_bytes()).await {
+                    Some(SshMsg::Write(data)) => {
+                        if let Err(e) = channel.data(&data[..]).await {
                             println!("发送数据失败: {}", e);
-                            break;
+                            break SessionEnd::Lost(format!("发送数据失败: {}", e));
                         }
                     },
-                    SshMsg::Resize { cols, rows } => {
+                    Some(SshMsg::Resize { cols, rows }) => {
+                        *size = (cols, rows);
                         if let Err(e) = channel.window_change(cols as u32, rows as u32, 0, 0).await {
                             println!("调整窗口大小失败: {}", e);
                         }
                     },
-                    SshMsg::Close => {
+                    Some(SshMsg::Reconnect) => {
+                        println!("收到重连请求");
+                        break SessionEnd::Restart;
+                    },
+                    Some(SshMsg::Close) | None => {
                         println!("收到关闭信号");
-                        break;
+                        break SessionEnd::Closed;
                     },
-                    SshMsg::ExecuteCommand { command, response_sender } => {
-                        // 执行系统监控命令
-                        let result = execute_monitoring_command(&session, &command).await;
-                        let _ = response_sender.send(result);
-                    }
                 }
             },
-            
-            else => {
-                // 所有选项都完成，退出循环
-                break;
-            }
         }
-    }
-    
-    // 关闭通道
-    let _ = channel.eof().await;
-    let _ = channel.close().await;
-    
-    // 断开会话
-    let _ = session.disconnect(Disconnect::ByApplication, "", "").await;
-    
-    Ok(())
-}
-
-// 执行系统监控命令
-async fn execute_monitoring_command(session: &client::Handle<Client>, command: &str) -> Result<String, String> {
-    // 创建新的通道执行命令
-    let mut channel = match session.channel_open_session().await {
-        Ok(channel) => channel,
-        Err(e) => return Err(format!("创建通道失败: {}", e)),
     };
 
-    // 执行命令
-    if let Err(e) = channel.exec(true, command.as_bytes()).await {
-        return Err(format!("执行命令失败: {}", e));
-    }
+    framer.finish(window, binary.load(Ordering::Relaxed), flow);
 
-    // 读取输出
-    let mut output = String::new();
-    let mut code = None;
-    
-    while let Some(msg) = channel.wait().await {
-        match msg {
-            ChannelMsg::Data { data } => {
-                output.push_str(&String::from_utf8_lossy(&data));
-            },
-            ChannelMsg::ExitStatus { exit_status } => {
-                code = Some(exit_status);
-            },
-            ChannelMsg::Eof => {
-                break;
-            },
-            _ => {}
-        }
-    }
+    // 关闭终端通道；连接本身由连接管理器在无使用者时断开
+    let _ = channel.eof().await;
+    let _ = channel.close().await;
 
-    if code == Some(0) || code.is_none() {
-        Ok(output.trim().to_string())
-    } else {
-        Err(format!("命令执行失败，退出码: {:?}", code))
-    }
+    Ok(end)
 }
 
-// 通过SSH终端执行命令（供系统监控使用）
+// 获取SSH终端所使用的共享连接
+pub fn terminal_connection(terminal_id: &str) -> Option<Arc<SshConnection>> {
+    ssh_session::connection_for(&ssh_session::terminal_consumer(terminal_id))
+}
+
+// 通过SSH终端的连接执行命令（供系统监控使用）
 #[allow(dead_code)]
 pub async fn execute_command_via_terminal(terminal_id: &str, command: String) -> Result<String, String> {
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    
-    // 获取终端并发送命令
-    {
-        let terminals = SSH_TERMINALS.lock();
-        if let Some(terminal) = terminals.get(terminal_id) {
-            if let Err(e) = terminal.sender.send(SshMsg::ExecuteCommand { 
-                command, 
-                response_sender: tx 
-            }) {
-                return Err(format!("发送命令失败: {}", e));
-            }
-        } else {
-            return Err("SSH终端未找到".to_string());
-        }
-    }
-    
-    // 等待命令执行结果
-    match rx.await {
-        Ok(result) => result,
-        Err(_) => Err("命令执行超时".to_string()),
+    match terminal_connection(terminal_id) {
+        Some(connection) => connection.exec(&command).await,
+        None => Err("SSH终端未找到".to_string()),
     }
 }
 
-// 向SSH终端写入数据
+// 向SSH终端写入文本（data）或原始字节（bytes）
 #[tauri::command]
-pub fn write_ssh_terminal(id: String, data: String) -> Result<(), String> {
+pub fn write_ssh_terminal(id: String, data: Option<String>, bytes: Option<Vec<u8>>) -> Result<(), String> {
+    let input = input_bytes(data, bytes)?;
+    crate::recorder::record_input(&id, &input);
     let terminals = SSH_TERMINALS.lock();
     if let Some(terminal) = terminals.get(&id) {
-        terminal.sender.send(SshMsg::Write(data)).map_err(|e| e.to_string())
+        terminal.sender.send(SshMsg::Write(input)).map_err(|e| e.to_string())
     } else {
         Err("SSH终端未找到".into())
     }
@@ -355,6 +516,7 @@ pub fn write_ssh_terminal(id: String, data: String) -> Result<(), String> {
 // 调整SSH终端大小
 #[tauri::command]
 pub fn resize_ssh_terminal(id: String, cols: u16, rows: u16) -> Result<(), String> {
+    crate::recorder::record_resize(&id, cols, rows);
     let terminals = SSH_TERMINALS.lock();
     if let Some(terminal) = terminals.get(&id) {
         terminal.sender.send(SshMsg::Resize { cols, rows }).map_err(|e| e.to_string())
@@ -363,6 +525,28 @@ pub fn resize_ssh_terminal(id: String, cols: u16, rows: u16) -> Result<(), Strin
     }
 }
 
+// 切换输出为原始字节（ssh_bytes://{id}）或文本（ssh_data://{id}）
+#[tauri::command]
+pub fn set_ssh_binary_mode(id: String, enabled: bool) -> Result<(), String> {
+    let terminals = SSH_TERMINALS.lock();
+    if let Some(terminal) = terminals.get(&id) {
+        terminal.binary.store(enabled, Ordering::Relaxed);
+        Ok(())
+    } else {
+        Err("SSH终端未找到".into())
+    }
+}
+
+// 请求SSH终端在同一ID下重新连接
+pub fn request_reconnect(id: &str) -> Result<(), String> {
+    let terminals = SSH_TERMINALS.lock();
+    if let Some(terminal) = terminals.get(id) {
+        terminal.sender.send(SshMsg::Reconnect).map_err(|e| e.to_string())
+    } else {
+        Err("SSH终端未找到".into())
+    }
+}
+
 // 关闭SSH终端
 #[tauri::command]
 pub fn close_ssh_terminal(id: String) -> Result<(), String> {
@@ -0,0 +1,627 @@
+// Telnet 终端：选项协商（NAWS、TTYPE、ECHO、SGA、BINARY），命令和事件与SSH终端一致（ssh_data://{id}、ssh_exit://{id}）
+use directories::ProjectDirs;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use crate::term_data::{input_bytes, FlowControl, OutputFramer};
+
+// Telnet 命令
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+// Telnet 选项
+const OPT_BINARY: u8 = 0;
+const OPT_ECHO: u8 = 1;
+const OPT_SGA: u8 = 3;
+const OPT_TTYPE: u8 = 24;
+const OPT_NAWS: u8 = 31;
+
+// TTYPE 子协商
+const TTYPE_IS: u8 = 0;
+const TTYPE_SEND: u8 = 1;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+// 子协商内容上限，超过后丢弃整段直到 IAC SE
+const MAX_SUBNEGOTIATION: usize = 1024;
+
+// Telnet 配置文件结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelnetProfileMeta {
+    pub id: String,
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+    // 通过 TTYPE 上报的终端类型，默认 xterm-256color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+}
+
+fn default_port() -> u16 {
+    23
+}
+
+// 解析器状态
+#[derive(Clone, Copy)]
+enum ParseState {
+    Data,
+    Iac,
+    Option(u8),
+    Sub,
+    SubIac,
+    // 子协商过长，跳过剩余内容
+    Discard,
+    DiscardIac,
+    // 收到 CR，需要丢弃其后的 NUL
+    Cr,
+}
+
+// Telnet 协议处理：拆分数据与命令、完成选项协商
+struct TelnetProtocol {
+    state: ParseState,
+    sub: Vec<u8>,
+    // 本端已启用的选项（WILL 已确认）
+    local: [bool; 256],
+    // 对端已启用的选项（DO 已确认）
+    remote: [bool; 256],
+    // 本端主动发出、尚未得到回应的 WILL / DO
+    pending_local: [bool; 256],
+    pending_remote: [bool; 256],
+    term: String,
+    size: (u16, u16),
+}
+
+impl TelnetProtocol {
+    fn new(term: String, size: (u16, u16)) -> Self {
+        TelnetProtocol {
+            state: ParseState::Data,
+            sub: Vec::new(),
+            local: [false; 256],
+            remote: [false; 256],
+            pending_local: [false; 256],
+            pending_remote: [false; 256],
+            term,
+            size,
+        }
+    }
+
+    // 连接建立后主动提出的选项
+    fn initial_negotiation(&mut self) -> Vec<u8> {
+        self.pending_local[OPT_NAWS as usize] = true;
+        self.pending_local[OPT_TTYPE as usize] = true;
+        self.pending_remote[OPT_SGA as usize] = true;
+        vec![IAC, WILL, OPT_NAWS, IAC, WILL, OPT_TTYPE, IAC, DO, OPT_SGA]
+    }
+
+    fn supports_local(option: u8) -> bool {
+        matches!(option, OPT_NAWS | OPT_TTYPE | OPT_BINARY | OPT_SGA)
+    }
+
+    fn supports_remote(option: u8) -> bool {
+        matches!(option, OPT_ECHO | OPT_SGA | OPT_BINARY)
+    }
+
+    // 处理收到的数据：终端数据写入 output，需要回复服务器的内容写入 reply
+    fn receive(&mut self, input: &[u8], output: &mut Vec<u8>, reply: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match self.state {
+                ParseState::Data | ParseState::Cr => match byte {
+                    IAC => ParseState::Iac,
+                    0 if matches!(self.state, ParseState::Cr) => ParseState::Data,
+                    b'\r' if !self.remote[OPT_BINARY as usize] => {
+                        output.push(byte);
+                        ParseState::Cr
+                    }
+                    _ => {
+                        output.push(byte);
+                        ParseState::Data
+                    }
+                },
+                ParseState::Iac => match byte {
+                    IAC => {
+                        output.push(IAC);
+                        ParseState::Data
+                    }
+                    DO | DONT | WILL | WONT => ParseState::Option(byte),
+                    SB => {
+                        self.sub.clear();
+                        ParseState::Sub
+                    }
+                    // NOP、GA 等其他命令忽略
+                    _ => ParseState::Data,
+                },
+                ParseState::Option(command) => {
+                    self.negotiate(command, byte, reply);
+                    ParseState::Data
+                }
+                ParseState::Sub => match byte {
+                    IAC => ParseState::SubIac,
+                    _ => self.push_sub(byte),
+                },
+                ParseState::SubIac => match byte {
+                    SE => {
+                        self.subnegotiation(reply);
+                        ParseState::Data
+                    }
+                    IAC => self.push_sub(IAC),
+                    _ => ParseState::Sub,
+                },
+                ParseState::Discard => match byte {
+                    IAC => ParseState::DiscardIac,
+                    _ => ParseState::Discard,
+                },
+                ParseState::DiscardIac => match byte {
+                    SE => ParseState::Data,
+                    _ => ParseState::Discard,
+                },
+            };
+        }
+    }
+
+    // 追加子协商内容，超过上限时清空缓冲并丢弃整段
+    fn push_sub(&mut self, byte: u8) -> ParseState {
+        if self.sub.len() >= MAX_SUBNEGOTIATION {
+            self.sub.clear();
+            return ParseState::Discard;
+        }
+        self.sub.push(byte);
+        ParseState::Sub
+    }
+
+    // 选项协商：只在状态变化时回复，避免协商循环；对本端主动请求的回应不再回复
+    fn negotiate(&mut self, command: u8, option: u8, reply: &mut Vec<u8>) {
+        let index = option as usize;
+        match command {
+            DO => {
+                if !Self::supports_local(option) {
+                    reply.extend_from_slice(&[IAC, WONT, option]);
+                    return;
+                }
+                if !self.local[index] {
+                    self.local[index] = true;
+                    if !std::mem::take(&mut self.pending_local[index]) {
+                        reply.extend_from_slice(&[IAC, WILL, option]);
+                    }
+                }
+                if option == OPT_NAWS {
+                    reply.extend_from_slice(&self.window_size());
+                }
+            }
+            DONT => {
+                if std::mem::take(&mut self.pending_local[index]) {
+                    return;
+                }
+                if self.local[index] {
+                    self.local[index] = false;
+                    reply.extend_from_slice(&[IAC, WONT, option]);
+                }
+            }
+            WILL => {
+                if !Self::supports_remote(option) {
+                    reply.extend_from_slice(&[IAC, DONT, option]);
+                    return;
+                }
+                if !self.remote[index] {
+                    self.remote[index] = true;
+                    if !std::mem::take(&mut self.pending_remote[index]) {
+                        reply.extend_from_slice(&[IAC, DO, option]);
+                    }
+                }
+            }
+            WONT => {
+                if std::mem::take(&mut self.pending_remote[index]) {
+                    return;
+                }
+                if self.remote[index] {
+                    self.remote[index] = false;
+                    reply.extend_from_slice(&[IAC, DONT, option]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn subnegotiation(&mut self, reply: &mut Vec<u8>) {
+        if self.sub.as_slice() == [OPT_TTYPE, TTYPE_SEND] && self.local[OPT_TTYPE as usize] {
+            reply.extend_from_slice(&[IAC, SB, OPT_TTYPE, TTYPE_IS]);
+            reply.extend_from_slice(self.term.as_bytes());
+            reply.extend_from_slice(&[IAC, SE]);
+        }
+    }
+
+    // NAWS 子协商：宽高各两字节，其中的 255 需要转义
+    fn window_size(&self) -> Vec<u8> {
+        let (cols, rows) = self.size;
+        let mut message = vec![IAC, SB, OPT_NAWS];
+        for byte in cols.to_be_bytes().into_iter().chain(rows.to_be_bytes()) {
+            message.push(byte);
+            if byte == IAC {
+                message.push(IAC);
+            }
+        }
+        message.extend_from_slice(&[IAC, SE]);
+        message
+    }
+
+    // 窗口大小变化，NAWS 已启用时返回需要发送的子协商
+    fn resize(&mut self, cols: u16, rows: u16) -> Option<Vec<u8>> {
+        self.size = (cols, rows);
+        self.local[OPT_NAWS as usize].then(|| self.window_size())
+    }
+
+    // 编码用户输入：转义 IAC，非二进制模式下单独的 CR 补 NUL
+    fn encode_input(&self, input: &[u8]) -> Vec<u8> {
+        let binary = self.local[OPT_BINARY as usize];
+        let mut encoded = Vec::with_capacity(input.len());
+        for (i, &byte) in input.iter().enumerate() {
+            encoded.push(byte);
+            if byte == IAC {
+                encoded.push(IAC);
+            } else if byte == b'\r' && !binary && input.get(i + 1) != Some(&b'\n') {
+                encoded.push(0);
+            }
+        }
+        encoded
+    }
+}
+
+// Telnet 终端消息类型
+enum TelnetMsg {
+    Write(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+    Close,
+}
+
+struct TelnetTerminal {
+    sender: mpsc::UnboundedSender<TelnetMsg>,
+}
+
+// 全局 Telnet 终端管理
+static TELNET_TERMINALS: Lazy<Mutex<HashMap<String, TelnetTerminal>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 启动 Telnet 终端
+#[tauri::command]
+pub fn start_telnet_terminal(
+    window: tauri::Window,
+    id: String,
+    host: String,
+    port: Option<u16>,
+    term: Option<String>,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    let port = port.unwrap_or(23);
+    println!("开始Telnet连接: {}:{}", host, port);
+
+    let (tx, rx) = mpsc::unbounded_channel::<TelnetMsg>();
+    TELNET_TERMINALS.lock().insert(id.clone(), TelnetTerminal { sender: tx });
+    let flow = crate::term_data::register_flow(&id);
+    let protocol = TelnetProtocol::new(term.unwrap_or_else(|| "xterm-256color".to_string()), (cols, rows));
+
+    tauri::async_runtime::spawn(async move {
+        match run_telnet(&window, &id, &host, port, protocol, rx, &flow).await {
+            Ok(_) => println!("Telnet连接关闭: {}:{}", host, port),
+            Err(e) => {
+                println!("Telnet连接错误: {}", e);
+                let _ = window.emit("ssh_error", format!("{}: {}", id, e));
+            }
+        }
+        let _ = window.emit(&format!("ssh_exit://{}", id), "");
+
+        TELNET_TERMINALS.lock().remove(&id);
+        crate::term_data::unregister_flow(&id, &flow);
+        crate::recorder::stop(&id);
+        crate::session_log::close(&id);
+    });
+
+    Ok(())
+}
+
+async fn run_telnet(
+    window: &tauri::Window,
+    id: &str,
+    host: &str,
+    port: u16,
+    mut protocol: TelnetProtocol,
+    mut rx: mpsc::UnboundedReceiver<TelnetMsg>,
+    flow: &FlowControl,
+) -> Result<(), String> {
+    let mut stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| format!("连接 {}:{} 超时", host, port))?
+        .map_err(|e| format!("连接 {}:{} 失败: {}", host, port, e))?;
+    let _ = stream.set_nodelay(true);
+    println!("✓ Telnet连接成功");
+
+    stream
+        .write_all(&protocol.initial_negotiation())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut framer = OutputFramer::new(id, "ssh_data", "ssh_bytes");
+    let mut buf = vec![0u8; 8192];
+    let mut output = Vec::new();
+    let mut reply = Vec::new();
+
+    let result = loop {
+        let deadline = framer.deadline();
+        let paused = flow.is_paused();
+        tokio::select! {
+            // 发送攒够时间的输出帧
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(std::time::Instant::now).into()), if deadline.is_some() => {
+                framer.flush(window, false, flow);
+            },
+
+            // 达到高水位时暂停读取，等待前端确认
+            _ = flow.wait(), if paused => {},
+
+            read = stream.read(&mut buf), if !paused => {
+                let n = match read {
+                    Ok(0) => break Ok(()),
+                    Ok(n) => n,
+                    Err(e) => break Err(format!("读取数据失败: {}", e)),
+                };
+                flow.record_read(n);
+                output.clear();
+                reply.clear();
+                protocol.receive(&buf[..n], &mut output, &mut reply);
+                if !reply.is_empty() {
+                    if let Err(e) = stream.write_all(&reply).await {
+                        break Err(format!("发送协商失败: {}", e));
+                    }
+                }
+                if !output.is_empty() {
+                    framer.push(&output);
+                    if framer.is_full() {
+                        framer.flush(window, false, flow);
+                    }
+                }
+            },
+
+            msg = rx.recv() => {
+                match msg {
+                    Some(TelnetMsg::Write(data)) => {
+                        if let Err(e) = stream.write_all(&protocol.encode_input(&data)).await {
+                            break Err(format!("发送数据失败: {}", e));
+                        }
+                    },
+                    Some(TelnetMsg::Resize { cols, rows }) => {
+                        if let Some(message) = protocol.resize(cols, rows) {
+                            if let Err(e) = stream.write_all(&message).await {
+                                println!("调整窗口大小失败: {}", e);
+                            }
+                        }
+                    },
+                    Some(TelnetMsg::Close) | None => {
+                        println!("收到关闭信号");
+                        break Ok(());
+                    },
+                }
+            },
+        }
+    };
+
+    framer.finish(window, false, flow);
+    let _ = stream.shutdown().await;
+    result
+}
+
+// 向 Telnet 终端写入文本（data）或原始字节（bytes）
+#[tauri::command]
+pub fn write_telnet_terminal(id: String, data: Option<String>, bytes: Option<Vec<u8>>) -> Result<(), String> {
+    let input = input_bytes(data, bytes)?;
+    crate::recorder::record_input(&id, &input);
+    let terminals = TELNET_TERMINALS.lock();
+    if let Some(terminal) = terminals.get(&id) {
+        terminal.sender.send(TelnetMsg::Write(input)).map_err(|e| e.to_string())
+    } else {
+        Err("Telnet终端未找到".into())
+    }
+}
+
+// 调整 Telnet 终端大小（通过 NAWS 通知服务器）
+#[tauri::command]
+pub fn resize_telnet_terminal(id: String, cols: u16, rows: u16) -> Result<(), String> {
+    crate::recorder::record_resize(&id, cols, rows);
+    let terminals = TELNET_TERMINALS.lock();
+    if let Some(terminal) = terminals.get(&id) {
+        terminal.sender.send(TelnetMsg::Resize { cols, rows }).map_err(|e| e.to_string())
+    } else {
+        Err("Telnet终端未找到".into())
+    }
+}
+
+// 关闭 Telnet 终端
+#[tauri::command]
+pub fn close_telnet_terminal(id: String) -> Result<(), String> {
+    let terminals = TELNET_TERMINALS.lock();
+    if let Some(terminal) = terminals.get(&id) {
+        let _ = terminal.sender.send(TelnetMsg::Close);
+        Ok(())
+    } else {
+        Err("Telnet终端未找到".into())
+    }
+}
+
+// 获取 Telnet 配置文件存储目录
+pub fn telnet_profiles_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    let proj = ProjectDirs::from("com", "Termlink", "Termlink").ok_or("no project dirs")?;
+    let dir = proj.config_dir().join("telnet_profiles");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+// 保存 Telnet 配置
+#[tauri::command]
+pub fn save_telnet_profile(app: AppHandle, profile: TelnetProfileMeta) -> Result<(), String> {
+    let dir = telnet_profiles_dir(&app)?;
+    let path = dir.join(format!("{}.json", profile.id));
+    let data = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// 列出所有 Telnet 配置
+#[tauri::command]
+pub fn list_telnet_profiles(app: AppHandle) -> Result<Vec<TelnetProfileMeta>, String> {
+    let dir = telnet_profiles_dir(&app)?;
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        if entry.path().extension().and_then(|s| s.to_str()) == Some("json") {
+            if let Ok(txt) = fs::read_to_string(entry.path()) {
+                if let Ok(meta) = serde_json::from_str::<TelnetProfileMeta>(&txt) {
+                    out.push(meta);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+// 删除 Telnet 配置
+#[tauri::command]
+pub fn delete_telnet_profile(app: AppHandle, profile_id: String) -> Result<(), String> {
+    let path = telnet_profiles_dir(&app)?.join(format!("{}.json", profile_id));
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol() -> TelnetProtocol {
+        TelnetProtocol::new("xterm-256color".to_string(), (80, 24))
+    }
+
+    fn receive(protocol: &mut TelnetProtocol, input: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut output = Vec::new();
+        let mut reply = Vec::new();
+        protocol.receive(input, &mut output, &mut reply);
+        (output, reply)
+    }
+
+    #[test]
+    fn acknowledged_initial_options_are_not_repeated() {
+        let mut protocol = protocol();
+        assert_eq!(
+            protocol.initial_negotiation(),
+            [IAC, WILL, OPT_NAWS, IAC, WILL, OPT_TTYPE, IAC, DO, OPT_SGA]
+        );
+        let (output, reply) = receive(&mut protocol, &[IAC, DO, OPT_TTYPE, IAC, WILL, OPT_SGA]);
+        assert!(output.is_empty());
+        assert!(reply.is_empty());
+        // DO NAWS 只回复窗口大小
+        let (_, reply) = receive(&mut protocol, &[IAC, DO, OPT_NAWS]);
+        assert_eq!(reply, [IAC, SB, OPT_NAWS, 0, 80, 0, 24, IAC, SE]);
+    }
+
+    #[test]
+    fn unsupported_options_are_refused() {
+        let mut protocol = protocol();
+        let (_, reply) = receive(&mut protocol, &[IAC, DO, OPT_ECHO, IAC, WILL, OPT_NAWS]);
+        assert_eq!(reply, [IAC, WONT, OPT_ECHO, IAC, DONT, OPT_NAWS]);
+    }
+
+    #[test]
+    fn remote_echo_is_accepted_once() {
+        let mut protocol = protocol();
+        let (_, reply) = receive(&mut protocol, &[IAC, WILL, OPT_ECHO]);
+        assert_eq!(reply, [IAC, DO, OPT_ECHO]);
+        let (_, reply) = receive(&mut protocol, &[IAC, WILL, OPT_ECHO]);
+        assert!(reply.is_empty());
+        let (_, reply) = receive(&mut protocol, &[IAC, WONT, OPT_ECHO]);
+        assert_eq!(reply, [IAC, DONT, OPT_ECHO]);
+    }
+
+    #[test]
+    fn terminal_type_is_reported() {
+        let mut protocol = protocol();
+        protocol.initial_negotiation();
+        let (_, reply) = receive(
+            &mut protocol,
+            &[IAC, DO, OPT_TTYPE, IAC, SB, OPT_TTYPE, TTYPE_SEND, IAC, SE],
+        );
+        let mut expected = vec![IAC, SB, OPT_TTYPE, TTYPE_IS];
+        expected.extend_from_slice(b"xterm-256color");
+        expected.extend_from_slice(&[IAC, SE]);
+        assert_eq!(reply, expected);
+    }
+
+    #[test]
+    fn data_is_unescaped() {
+        let mut protocol = protocol();
+        let input = [b'a', IAC, IAC, b'b', b'\r', 0, b'c', b'\r', b'\n'];
+        let (output, _) = receive(&mut protocol, &input);
+        assert_eq!(output, [b'a', IAC, b'b', b'\r', b'c', b'\r', b'\n']);
+    }
+
+    #[test]
+    fn commands_split_across_reads() {
+        let mut protocol = protocol();
+        let (output, reply) = receive(&mut protocol, &[b'a', IAC]);
+        assert_eq!(output, b"a");
+        assert!(reply.is_empty());
+        let (output, reply) = receive(&mut protocol, &[WILL, OPT_ECHO, b'b']);
+        assert_eq!(output, b"b");
+        assert_eq!(reply, [IAC, DO, OPT_ECHO]);
+    }
+
+    #[test]
+    fn oversized_subnegotiation_is_dropped() {
+        let mut protocol = protocol();
+        protocol.initial_negotiation();
+        receive(&mut protocol, &[IAC, DO, OPT_TTYPE]);
+        let mut input = vec![IAC, SB, OPT_TTYPE, TTYPE_SEND];
+        input.extend(vec![b'x'; MAX_SUBNEGOTIATION * 2]);
+        input.extend_from_slice(&[IAC, IAC, IAC, SE, b'o', b'k']);
+        let (output, reply) = receive(&mut protocol, &input);
+        assert_eq!(output, b"ok");
+        assert!(reply.is_empty());
+        // 之后的子协商仍可正常处理
+        let (_, reply) = receive(&mut protocol, &[IAC, SB, OPT_TTYPE, TTYPE_SEND, IAC, SE]);
+        assert!(reply.starts_with(&[IAC, SB, OPT_TTYPE, TTYPE_IS]));
+    }
+
+    #[test]
+    fn window_size_escapes_iac() {
+        let mut protocol = protocol();
+        assert_eq!(protocol.resize(255, 24), None);
+        receive(&mut protocol, &[IAC, DO, OPT_NAWS]);
+        assert_eq!(
+            protocol.resize(255, 300),
+            Some(vec![IAC, SB, OPT_NAWS, 0, IAC, IAC, 1, 44, IAC, SE])
+        );
+    }
+
+    #[test]
+    fn input_is_escaped() {
+        let mut protocol = protocol();
+        assert_eq!(
+            protocol.encode_input(&[b'a', IAC, b'\r', b'\r', b'\n']),
+            [b'a', IAC, IAC, b'\r', 0, b'\r', b'\n']
+        );
+        receive(&mut protocol, &[IAC, DO, OPT_BINARY]);
+        assert_eq!(protocol.encode_input(&[b'\r', IAC]), [b'\r', IAC, IAC]);
+    }
+}
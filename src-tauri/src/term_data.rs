@@ -0,0 +1,461 @@
+// 终端数据通道：PTY 和 SSH 终端共用的输出解码、分帧、流控与输入参数处理
+use once_cell::sync::Lazy;
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use crossbeam_channel::RecvTimeoutError;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+// 输出分帧：最多攒 16ms 或 64KB 发送一次事件
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+pub const FRAME_MAX_BYTES: usize = 64 * 1024;
+// 前端未确认的数据超过此值时暂停读取
+pub const HIGH_WATER_MARK: usize = 1024 * 1024;
+
+// 增量 UTF-8 解码器：跨读取块的多字节字符会保留到下一块再解码
+#[derive(Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 解码一个数据块，末尾不完整的字符暂存；真正非法的字节替换为 U+FFFD
+    pub fn decode(&mut self, chunk: &[u8]) -> String {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(chunk);
+
+        let mut output = String::with_capacity(bytes.len());
+        let mut rest = &bytes[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    output.push_str(text);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, tail) = rest.split_at(e.valid_up_to());
+                    output.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            output.push(char::REPLACEMENT_CHARACTER);
+                            rest = &tail[len..];
+                        }
+                        None => {
+                            self.pending = tail.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    // 取出暂存的残余字节（切换到原始字节输出时使用）
+    pub fn take_pending(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending)
+    }
+
+    // 数据流结束时输出暂存的残余字节
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        String::from_utf8_lossy(&rest).to_string()
+    }
+}
+
+// 写入命令的输入：文本（data）或原始字节（bytes，如 ZMODEM 数据）
+pub fn input_bytes(data: Option<String>, bytes: Option<Vec<u8>>) -> Result<Vec<u8>, String> {
+    match (bytes, data) {
+        (Some(bytes), _) => Ok(bytes),
+        (None, Some(data)) => Ok(data.into_bytes()),
+        (None, None) => Err("缺少写入数据".into()),
+    }
+}
+
+// 每个终端会话的流量统计
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowMetrics {
+    pub bytes_read: u64,        // 从 PTY/通道读取的字节数
+    pub bytes_emitted: u64,     // 已发送给前端的字节数
+    pub frames: u64,            // 已发送的事件数
+    pub queued_bytes: usize,    // 已发送但前端尚未确认的字节数
+    pub peak_queued_bytes: usize,
+    pub pauses: u64,            // 因达到高水位而暂停读取的次数
+    pub dropped_bytes: u64,     // 丢弃的字节数（断线期间的输入、发送失败的输出）
+    pub flow_control: bool,     // 前端是否启用了确认（未确认过则不会暂停）
+}
+
+struct FlowState {
+    metrics: FlowMetrics,
+    paused: bool,
+    closed: bool,
+}
+
+// 输出流控：读取端在未确认数据超过高水位时等待前端确认
+pub struct FlowControl {
+    state: Mutex<FlowState>,
+    condvar: Condvar,
+    notify: tokio::sync::Notify,
+}
+
+impl FlowControl {
+    fn new() -> Self {
+        FlowControl {
+            state: Mutex::new(FlowState { metrics: FlowMetrics::default(), paused: false, closed: false }),
+            condvar: Condvar::new(),
+            notify: tokio::sync::Notify::new(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().paused
+    }
+
+    // 阻塞等待直到可以继续读取（PTY 读取线程使用）
+    pub fn wait_blocking(&self) {
+        let mut state = self.state.lock();
+        while state.paused && !state.closed {
+            self.condvar.wait(&mut state);
+        }
+    }
+
+    // 异步等待直到可以继续读取（SSH 会话使用）
+    pub async fn wait(&self) {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let state = self.state.lock();
+                if !state.paused || state.closed {
+                    return;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    pub fn record_read(&self, bytes: usize) {
+        self.state.lock().metrics.bytes_read += bytes as u64;
+    }
+
+    pub fn record_dropped(&self, bytes: usize) {
+        self.state.lock().metrics.dropped_bytes += bytes as u64;
+    }
+
+    fn record_emitted(&self, bytes: usize) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let metrics = &mut state.metrics;
+        metrics.bytes_emitted += bytes as u64;
+        metrics.frames += 1;
+        metrics.queued_bytes += bytes;
+        metrics.peak_queued_bytes = metrics.peak_queued_bytes.max(metrics.queued_bytes);
+        if metrics.flow_control && metrics.queued_bytes >= HIGH_WATER_MARK && !state.paused {
+            metrics.pauses += 1;
+            state.paused = true;
+        }
+    }
+
+    // 前端确认已处理的字节数；None 表示已发送的数据全部处理完毕
+    fn ack(&self, bytes: Option<usize>) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let metrics = &mut state.metrics;
+        metrics.flow_control = true;
+        metrics.queued_bytes = match bytes {
+            Some(n) => metrics.queued_bytes.saturating_sub(n),
+            None => 0,
+        };
+        if state.paused && metrics.queued_bytes < HIGH_WATER_MARK / 2 {
+            state.paused = false;
+            self.condvar.notify_all();
+            self.notify.notify_waiters();
+        }
+    }
+
+    // 会话结束：唤醒所有等待者
+    fn close(&self) {
+        self.state.lock().closed = true;
+        self.condvar.notify_all();
+        self.notify.notify_waiters();
+    }
+
+    pub fn metrics(&self) -> FlowMetrics {
+        self.state.lock().metrics.clone()
+    }
+}
+
+// 全局终端流控登记（PTY 与 SSH 终端的ID互不重复）
+static FLOWS: Lazy<Mutex<HashMap<String, Arc<FlowControl>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn register_flow(id: &str) -> Arc<FlowControl> {
+    let flow = Arc::new(FlowControl::new());
+    if let Some(old) = FLOWS.lock().insert(id.to_string(), flow.clone()) {
+        old.close();
+    }
+    flow
+}
+
+pub fn unregister_flow(id: &str, flow: &Arc<FlowControl>) {
+    let mut flows = FLOWS.lock();
+    if flows.get(id).map(|f| Arc::ptr_eq(f, flow)).unwrap_or(false) {
+        flows.remove(id);
+    }
+    flow.close();
+}
+
+// 输出分帧：缓存读取到的数据，按时间或大小上限合并为一个事件
+pub struct OutputFramer {
+    id: String,
+    text_event: String,
+    bytes_event: String,
+    decoder: Utf8Decoder,
+    buffer: Vec<u8>,
+    started: Option<Instant>,
+}
+
+impl OutputFramer {
+    // 事件名为 {text_prefix}://{id}（文本）和 {bytes_prefix}://{id}（原始字节模式）
+    pub fn new(id: &str, text_prefix: &str, bytes_prefix: &str) -> Self {
+        OutputFramer {
+            id: id.to_string(),
+            text_event: format!("{}://{}", text_prefix, id),
+            bytes_event: format!("{}://{}", bytes_prefix, id),
+            decoder: Utf8Decoder::new(),
+            buffer: Vec::new(),
+            started: None,
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+        self.buffer.extend_from_slice(data);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.buffer.len() >= FRAME_MAX_BYTES
+    }
+
+    // 当前帧必须发送的时间点
+    pub fn deadline(&self) -> Option<Instant> {
+        self.started.map(|t| t + FRAME_INTERVAL)
+    }
+
+    pub fn flush(&mut self, window: &tauri::Window, binary: bool, flow: &FlowControl) {
+        self.started = None;
+        if self.buffer.is_empty() {
+            return;
+        }
+        let data = std::mem::take(&mut self.buffer);
+        let (sent, len) = if binary {
+            let mut bytes = self.decoder.take_pending();
+            bytes.extend_from_slice(&data);
+            let text = String::from_utf8_lossy(&bytes);
+            crate::recorder::record_output(&self.id, &text);
+            crate::session_log::append(&self.id, &data, &text);
+            let len = bytes.len();
+            (window.emit(&self.bytes_event, bytes).is_ok(), len)
+        } else {
+            // 原始日志记录解码前的字节
+            let text = self.decoder.decode(&data);
+            crate::session_log::append(&self.id, &data, &text);
+            if text.is_empty() {
+                return;
+            }
+            crate::recorder::record_output(&self.id, &text);
+            let len = text.len();
+            (window.emit(&self.text_event, text).is_ok(), len)
+        };
+        if sent {
+            flow.record_emitted(len);
+        } else {
+            flow.record_dropped(len);
+        }
+    }
+
+    // 数据流结束：发送剩余数据和解码器中残余的字节
+    pub fn finish(&mut self, window: &tauri::Window, binary: bool, flow: &FlowControl) {
+        self.flush(window, binary, flow);
+        let rest = self.decoder.finish();
+        if !rest.is_empty() {
+            crate::recorder::record_output(&self.id, &rest);
+            // 残余字节已写入原始日志
+            crate::session_log::append(&self.id, &[], &rest);
+            let _ = window.emit(&self.text_event, rest);
+        }
+    }
+}
+
+// 输出线程（阻塞读取的数据源使用）：把读取线程送来的数据合并成帧后发送，数据源关闭后结束
+pub fn spawn_output_thread(
+    window: tauri::Window,
+    id: String,
+    text_prefix: &'static str,
+    bytes_prefix: &'static str,
+    chunks: crossbeam_channel::Receiver<Vec<u8>>,
+    binary: Arc<AtomicBool>,
+    flow: Arc<FlowControl>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut framer = OutputFramer::new(&id, text_prefix, bytes_prefix);
+        loop {
+            let received = match framer.deadline() {
+                Some(deadline) => chunks.recv_deadline(deadline),
+                None => chunks.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(chunk) => {
+                    framer.push(&chunk);
+                    if framer.is_full() {
+                        framer.flush(&window, binary.load(Ordering::Relaxed), &flow);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => framer.flush(&window, binary.load(Ordering::Relaxed), &flow),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        framer.finish(&window, binary.load(Ordering::Relaxed), &flow);
+    })
+}
+
+// 前端确认已写入终端的输出字节数（文本按 UTF-8 字节计）；bytes 为空表示全部确认
+#[tauri::command]
+pub fn ack_terminal_output(id: String, bytes: Option<usize>) -> Result<(), String> {
+    match FLOWS.lock().get(&id) {
+        Some(flow) => {
+            flow.ack(bytes);
+            Ok(())
+        }
+        None => Err("终端未找到".into()),
+    }
+}
+
+// 获取终端输出流量统计；id 为空时返回所有终端
+#[tauri::command]
+pub fn get_terminal_flow_metrics(id: Option<String>) -> Result<HashMap<String, FlowMetrics>, String> {
+    let flows = FLOWS.lock();
+    match id {
+        Some(id) => match flows.get(&id) {
+            Some(flow) => Ok(HashMap::from([(id, flow.metrics())])),
+            None => Err("终端未找到".into()),
+        },
+        None => Ok(flows.iter().map(|(id, flow)| (id.clone(), flow.metrics())).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 在指定位置拆成两块解码，结果应与整体解码一致
+    fn decode_split(bytes: &[u8], split: usize) -> String {
+        let mut decoder = Utf8Decoder::new();
+        let mut output = decoder.decode(&bytes[..split]);
+        output.push_str(&decoder.decode(&bytes[split..]));
+        output.push_str(&decoder.finish());
+        output
+    }
+
+    #[test]
+    fn multibyte_split_across_chunks() {
+        for text in ["aé b", "a中 b", "a😀 b"] {
+            let bytes = text.as_bytes();
+            for split in 0..=bytes.len() {
+                assert_eq!(decode_split(bytes, split), text, "split at {}", split);
+            }
+        }
+    }
+
+    #[test]
+    fn incomplete_char_is_held_back() {
+        let mut decoder = Utf8Decoder::new();
+        let bytes = "😀".as_bytes();
+        assert_eq!(decoder.decode(&bytes[..1]), "");
+        assert_eq!(decoder.decode(&bytes[1..3]), "");
+        assert_eq!(decoder.decode(&bytes[3..]), "😀");
+        assert_eq!(decoder.finish(), "");
+    }
+
+    #[test]
+    fn invalid_bytes_are_replaced() {
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(b"a\xffb\xc3(c"), "a\u{fffd}b\u{fffd}(c");
+        // 非法字节之后的不完整字符仍保留到下一块
+        assert_eq!(decoder.decode(b"\xfe\xe4\xb8"), "\u{fffd}");
+        assert_eq!(decoder.decode(b"\xad!"), "中!");
+    }
+
+    #[test]
+    fn unfinished_bytes_flushed_at_end() {
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.decode(b"x\xe4\xb8"), "x");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+        assert_eq!(decoder.finish(), "");
+    }
+
+    #[test]
+    fn no_pause_before_first_ack() {
+        let flow = FlowControl::new();
+        flow.record_emitted(HIGH_WATER_MARK * 2);
+        assert!(!flow.is_paused());
+        assert_eq!(flow.metrics().pauses, 0);
+    }
+
+    #[test]
+    fn pauses_at_high_water_mark_and_resumes_after_ack() {
+        let flow = FlowControl::new();
+        flow.ack(None);
+        flow.record_emitted(HIGH_WATER_MARK - 1);
+        assert!(!flow.is_paused());
+        flow.record_emitted(1);
+        assert!(flow.is_paused());
+        flow.record_emitted(100);
+        assert_eq!(flow.metrics().pauses, 1);
+
+        // 确认后仍在半水位之上，保持暂停
+        flow.ack(Some(HIGH_WATER_MARK / 2));
+        assert!(flow.is_paused());
+        flow.ack(Some(200));
+        assert!(!flow.is_paused());
+        let metrics = flow.metrics();
+        assert_eq!(metrics.queued_bytes, HIGH_WATER_MARK / 2 - 100);
+        assert_eq!(metrics.peak_queued_bytes, HIGH_WATER_MARK + 100);
+        // 未暂停时不阻塞
+        flow.wait_blocking();
+    }
+
+    #[test]
+    fn oversized_ack_does_not_underflow() {
+        let flow = FlowControl::new();
+        flow.ack(None);
+        flow.record_emitted(10);
+        flow.ack(Some(usize::MAX));
+        assert_eq!(flow.metrics().queued_bytes, 0);
+        flow.record_emitted(5);
+        assert_eq!(flow.metrics().queued_bytes, 5);
+    }
+
+    #[test]
+    fn close_releases_blocked_reader() {
+        let flow = Arc::new(FlowControl::new());
+        flow.ack(None);
+        flow.record_emitted(HIGH_WATER_MARK);
+        let waiter = {
+            let flow = flow.clone();
+            thread::spawn(move || flow.wait_blocking())
+        };
+        flow.close();
+        waiter.join().unwrap();
+    }
+}
@@ -1,15 +1,101 @@
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, PtySize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, io::Read, thread};
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
+use crate::term_data::input_bytes;
+
+// 关闭时先发送 SIGHUP，超过此时间仍未退出则强制结束
+const CLOSE_GRACE_PERIOD: Duration = Duration::from_secs(3);
 
 enum PtyMsg {
-  Write(String),
+  Write(Vec<u8>),
   Resize { cols: u16, rows: u16 },
+  Close,
+  // 子进程已退出（退出码，等待失败时为 None）
+  Exited(Option<u32>),
+}
+
+struct PtySession {
+  sender: crossbeam_channel::Sender<PtyMsg>,
+  // 原始字节模式：输出以 pty_bytes://{id} 事件发送字节数组（用于 ZMODEM 等二进制协议）
+  binary: Arc<AtomicBool>,
+  pid: Option<u32>,
+  command: String,
+  cwd: Option<String>,
+  started: Instant,
+  started_at: String,
+}
+
+// list_ptys 返回的终端信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyInfo {
+  pub id: String,
+  pub pid: Option<u32>,
+  pub command: String,
+  pub cwd: Option<String>,
+  pub started_at: String,
+  pub uptime_secs: u64,
+}
+
+// pty_exit://{id} 事件内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PtyExit {
+  exit_code: Option<u32>,
+}
+
+// 启动失败：通知前端并清理会话
+fn fail_pty(window: &tauri::Window, id: &str, flow: &Arc<crate::term_data::FlowControl>, err: impl std::fmt::Display) {
+  let _ = window.emit("pty_error", format!("{}: {}", id, err));
+  PTY_SESSIONS.lock().remove(id);
+  crate::term_data::unregister_flow(id, flow);
 }
 
-static PTY_SENDERS: Lazy<Mutex<HashMap<String, crossbeam_channel::Sender<PtyMsg>>>> =
+// 结束子进程：Unix 上先发送 SIGHUP，宽限期后仍未退出再强制结束；返回退出码
+fn terminate_child(
+  pid: Option<u32>,
+  killer: &mut Box<dyn ChildKiller + Send + Sync>,
+  rx: &crossbeam_channel::Receiver<PtyMsg>,
+) -> Option<u32> {
+  #[cfg(unix)]
+  let hangup_sent = match pid {
+    Some(pid) => unsafe { libc::kill(pid as libc::pid_t, libc::SIGHUP) == 0 },
+    None => false,
+  };
+  #[cfg(not(unix))]
+  let hangup_sent = {
+    let _ = pid;
+    false
+  };
+  if !hangup_sent {
+    let _ = killer.kill();
+  }
+
+  let wait_exit = |timeout: Duration| {
+    let deadline = Instant::now() + timeout;
+    loop {
+      match rx.recv_deadline(deadline) {
+        Ok(PtyMsg::Exited(code)) => return Some(code),
+        Ok(_) => continue,
+        Err(_) => return None,
+      }
+    }
+  };
+  if let Some(code) = wait_exit(CLOSE_GRACE_PERIOD) {
+    return code;
+  }
+  println!("PTY子进程未在宽限期内退出，强制结束");
+  let _ = killer.kill();
+  wait_exit(Duration::from_secs(1)).flatten()
+}
+
+static PTY_SESSIONS: Lazy<Mutex<HashMap<String, PtySession>>> =
   Lazy::new(|| Mutex::new(HashMap::new()));
 
 #[tauri::command]
@@ -21,16 +107,47 @@ pub fn start_pty(
   program: Option<String>,
   args: Option<Vec<String>>,
   cwd: Option<String>,
+  profile_id: Option<String>,
 ) -> Result<(), String> {
+  // 指定 Shell 配置时使用其程序、参数、环境变量和工作目录（显式传入的 cwd 优先）
+  let profile = match profile_id.as_deref() {
+    Some(pid) => Some(crate::shell::find_shell_profile(window.app_handle(), pid)?),
+    None => None,
+  };
+  let (program, args) = match &profile {
+    Some(p) => (Some(p.program.clone()), Some(p.launch_args())),
+    None => (program, args),
+  };
+  let cwd = cwd.or_else(|| profile.as_ref().and_then(|p| p.working_dir()));
+  let env = profile.map(|p| p.env).unwrap_or_default();
+
   let (tx, rx) = crossbeam_channel::unbounded::<PtyMsg>();
-  PTY_SENDERS.lock().insert(id.clone(), tx);
+  let exit_tx = tx.clone();
+  let binary = Arc::new(AtomicBool::new(false));
+  let flow = crate::term_data::register_flow(&id);
+  let default_shell = if cfg!(windows) { "cmd.exe" } else { "/bin/bash" };
+  let mut command = program.clone().unwrap_or_else(|| default_shell.to_string());
+  for arg in args.iter().flatten() {
+    command.push(' ');
+    command.push_str(arg);
+  }
+  let session = PtySession {
+    sender: tx,
+    binary: binary.clone(),
+    pid: None,
+    command,
+    cwd: cwd.clone().or_else(|| std::env::current_dir().ok().map(|d| d.to_string_lossy().to_string())),
+    started: Instant::now(),
+    started_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+  };
+  PTY_SESSIONS.lock().insert(id.clone(), session);
 
   thread::spawn(move || {
     let pty_system = native_pty_system();
     let pair = match pty_system.openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 }) {
       Ok(p) => p,
       Err(e) => {
-        let _ = window.emit("pty_error", format!("{}: {}", id, e));
+        fail_pty(&window, &id, &flow, e);
         return;
       }
     };
@@ -47,11 +164,12 @@ pub fn start_pty(
       CommandBuilder::new("/bin/bash")
     };
     if let Some(dir) = cwd { cmd.cwd(dir); }
+    for (key, value) in env { cmd.env(key, value); }
 
-    let _child = match pair.slave.spawn_command(cmd) {
+    let mut child = match pair.slave.spawn_command(cmd) {
       Ok(c) => c,
       Err(e) => {
-        let _ = window.emit("pty_error", format!("{}: {}", id, e));
+        fail_pty(&window, &id, &flow, e);
         return;
       }
     };
@@ -59,7 +177,7 @@ pub fn start_pty(
     let mut reader = match pair.master.try_clone_reader() {
       Ok(r) => r,
       Err(e) => {
-        let _ = window.emit("pty_error", format!("{}: {}", id, e));
+        fail_pty(&window, &id, &flow, e);
         return;
       }
     };
@@ -67,52 +185,91 @@ pub fn start_pty(
     let mut writer = match pair.master.take_writer() {
       Ok(w) => w,
       Err(e) => {
-        let _ = window.emit("pty_error", format!("{}: {}", id, e));
+        fail_pty(&window, &id, &flow, e);
         return;
       }
     };
 
-    // Reader loop
-    let win_clone = window.clone();
-    let id_clone = id.clone();
-    let reader_thread = thread::spawn(move || {
+    // 记录进程ID，并在单独的线程中等待子进程退出
+    let pid = child.process_id();
+    if let Some(session) = PTY_SESSIONS.lock().get_mut(&id) {
+      session.pid = pid;
+    }
+    let mut killer = child.clone_killer();
+    thread::spawn(move || {
+      let code = child.wait().ok().map(|status| status.exit_code());
+      let _ = exit_tx.send(PtyMsg::Exited(code));
+    });
+
+    // Reader loop：达到高水位时暂停读取，直到前端确认
+    let reader_flow = flow.clone();
+    let (chunk_tx, chunk_rx) = crossbeam_channel::bounded::<Vec<u8>>(16);
+    thread::spawn(move || {
       let mut buf = [0u8; 4096];
       loop {
+        reader_flow.wait_blocking();
         match reader.read(&mut buf) {
           Ok(0) => break,
           Ok(n) => {
-            let chunk = String::from_utf8_lossy(&buf[..n]).to_string();
-            let _ = win_clone.emit(&format!("pty://{}", id_clone), chunk);
+            reader_flow.record_read(n);
+            if chunk_tx.send(buf[..n].to_vec()).is_err() { break; }
           }
           Err(_) => break,
         }
       }
     });
 
-    // Writer/resize loop
-    while let Ok(msg) = rx.recv() {
-      match msg {
-        PtyMsg::Write(data) => {
+    // Output loop：把读取到的数据合并成帧后发送
+    let reader_thread = crate::term_data::spawn_output_thread(
+      window.clone(),
+      id.clone(),
+      "pty",
+      "pty_bytes",
+      chunk_rx,
+      binary,
+      flow.clone(),
+    );
+
+    // Writer/resize loop，直到子进程退出或收到关闭请求
+    let exit_code = loop {
+      match rx.recv() {
+        Ok(PtyMsg::Write(data)) => {
           // Write user input as-is; Windows shells handle CR/LF themselves
-          let _ = std::io::Write::write_all(&mut writer, data.as_bytes());
+          let _ = std::io::Write::write_all(&mut writer, &data);
         }
-        PtyMsg::Resize { cols, rows } => {
+        Ok(PtyMsg::Resize { cols, rows }) => {
           let _ = pair.master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 });
         }
+        Ok(PtyMsg::Exited(code)) => break code,
+        Ok(PtyMsg::Close) => break terminate_child(pid, &mut killer, &rx),
+        Err(_) => break None,
       }
-    }
+    };
+    println!("PTY {} 已退出，退出码: {:?}", id, exit_code);
 
+    // 关闭PTY主端，读取线程随之结束；同时结束流控，唤醒可能因高水位暂停的读取线程
+    PTY_SESSIONS.lock().remove(&id);
+    drop(writer);
+    drop(pair);
+    crate::term_data::unregister_flow(&id, &flow);
     let _ = reader_thread.join();
-    let _ = window.emit(&format!("pty_exit://{}", id), "");
+    crate::recorder::stop(&id);
+    crate::session_log::close(&id);
+    let _ = window.emit(&format!("pty_exit://{}", id), PtyExit { exit_code });
   });
 
   Ok(())
 }
 
+// 写入文本（data）或原始字节（bytes）
 #[tauri::command]
-pub fn write_pty(id: String, data: String) -> Result<(), String> {
-  if let Some(tx) = PTY_SENDERS.lock().get(&id) {
-    tx.send(PtyMsg::Write(data)).map_err(|e| e.to_string())
+pub fn write_pty(id: String, data: Option<String>, bytes: Option<Vec<u8>>) -> Result<(), String> {
+  let input = input_bytes(data, bytes)?;
+  crate::recorder::record_input(&id, &input);
+  if let Some(session) = PTY_SESSIONS.lock().get(&id) {
+    session.sender.send(PtyMsg::Write(input)).map_err(|e| e.to_string())
+  } else if crate::serial::write(&id, input) {
+    Ok(())
   } else {
     Err("PTY not found".into())
   }
@@ -120,15 +277,54 @@ pub fn write_pty(id: String, data: String) -> Result<(), String> {
 
 #[tauri::command]
 pub fn resize_pty(id: String, cols: u16, rows: u16) -> Result<(), String> {
-  if let Some(tx) = PTY_SENDERS.lock().get(&id) {
-    tx.send(PtyMsg::Resize { cols, rows }).map_err(|e| e.to_string())
+  crate::recorder::record_resize(&id, cols, rows);
+  if let Some(session) = PTY_SESSIONS.lock().get(&id) {
+    session.sender.send(PtyMsg::Resize { cols, rows }).map_err(|e| e.to_string())
+  } else if crate::serial::exists(&id) {
+    // 串口没有窗口大小的概念
+    Ok(())
+  } else {
+    Err("PTY not found".into())
+  }
+}
+
+// 切换输出为原始字节（pty_bytes://{id}）或文本（pty://{id}）
+#[tauri::command]
+pub fn set_pty_binary_mode(id: String, enabled: bool) -> Result<(), String> {
+  if let Some(session) = PTY_SESSIONS.lock().get(&id) {
+    session.binary.store(enabled, Ordering::Relaxed);
+    Ok(())
   } else {
     Err("PTY not found".into())
   }
 }
 
+// 关闭终端：挂断子进程，退出后发送 pty_exit://{id}
 #[tauri::command]
 pub fn close_pty(id: String) -> Result<(), String> {
-  PTY_SENDERS.lock().remove(&id);
+  if let Some(session) = PTY_SESSIONS.lock().get(&id) {
+    let _ = session.sender.send(PtyMsg::Close);
+  } else {
+    crate::serial::close(&id);
+  }
   Ok(())
+}
+
+// 列出运行中的本地终端
+#[tauri::command]
+pub fn list_ptys() -> Vec<PtyInfo> {
+  let mut items: Vec<PtyInfo> = PTY_SESSIONS
+    .lock()
+    .iter()
+    .map(|(id, session)| PtyInfo {
+      id: id.clone(),
+      pid: session.pid,
+      command: session.command.clone(),
+      cwd: session.cwd.clone(),
+      started_at: session.started_at.clone(),
+      uptime_secs: session.started.elapsed().as_secs(),
+    })
+    .collect();
+  items.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+  items
 }
\ No newline at end of file
@@ -43,6 +43,8 @@ class SshService {
         port: profile.port || 22,
         username: profile.username,
         password: password,
+        privateKey: profile.private_key || null,
+        profileId: profile.id || null,
         cols: 80, 
         rows: 24
       });
@@ -115,6 +117,7 @@ class SshService {
         port: Number(sshData.port) || 22,
         username: sshData.username,
         password: sshData.password,
+        privateKey: sshData.usePrivateKey ? sshData.privateKey : null,
         cols: 80, 
         rows: 24
       });
@@ -162,17 +165,19 @@ class SshService {
         return null;
       }
       
-      // 直接创建独立的SFTP连接
+      // 创建SFTP连接（后端会复用同一配置的SSH连接）
       const sftpId = `sftp-${id}`;
       await invoke('connect_sftp', {
         connectionId: sftpId,
         host: profile.host,
         port: profile.port || 22,
         username: profile.username,
-        password: password || profile.password || null
+        password: password || profile.password || null,
+        privateKey: profile.private_key || null,
+        profileId: profile.id || null
       });
       
-      console.log(`独立SFTP连接已建立: ${sftpId}`);
+      console.log(`SFTP连接已建立: ${sftpId}`);
       return sftpId;
     } catch (error) {
       console.warn(`SFTP连接初始化失败:`, error);
@@ -284,6 +289,8 @@ class SshService {
         port: profile.port || 22,
         username: profile.username,
         password: password,
+        privateKey: profile.private_key || null,
+        profileId: profile.id || null,
         cols: 80, 
         rows: 24
       });